@@ -20,10 +20,12 @@ fn main() -> Result<(), Box<dyn Error>> {
             .unwrap(),
     );
 
+    // The read service is defined here rather than on the BSR, so that the reader always builds
+    // against the protocol it implements
     process_status(
         Command::new("buf")
             .arg("generate")
-            .arg("buf.build/gigantic-minecraft/seichi-timed-stats")
+            .arg("proto")
             .current_dir(env!("CARGO_MANIFEST_DIR"))
             .status()
             .unwrap(),
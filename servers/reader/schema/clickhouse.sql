@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS player_stats (
+    player_uuid UUID,
+    kind LowCardinality(String),
+    recorded_at DateTime64(3, 'UTC'),
+    value UInt64
+)
+ENGINE = ReplacingMergeTree
+PARTITION BY toYYYYMM(recorded_at)
+ORDER BY (kind, player_uuid, recorded_at);
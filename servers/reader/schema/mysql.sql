@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS player_stats (
+    player_uuid BINARY(16) NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    recorded_at DATETIME(3) NOT NULL,
+    value BIGINT UNSIGNED NOT NULL,
+    PRIMARY KEY (player_uuid, kind, recorded_at),
+    INDEX player_stats_by_time (kind, recorded_at)
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS player_stats (
+    player_uuid UUID NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    value BIGINT NOT NULL CHECK (value >= 0),
+    PRIMARY KEY (player_uuid, kind, recorded_at)
+);
+
+CREATE INDEX IF NOT EXISTS player_stats_by_time ON player_stats (kind, recorded_at);
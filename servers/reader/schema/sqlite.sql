@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS player_stats (
+    player_uuid BLOB NOT NULL,
+    kind TEXT NOT NULL,
+    recorded_at INTEGER NOT NULL,
+    value INTEGER NOT NULL CHECK (value >= 0),
+    PRIMARY KEY (player_uuid, kind, recorded_at)
+) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS player_stats_by_time ON player_stats (kind, recorded_at);
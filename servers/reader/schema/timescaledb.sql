@@ -0,0 +1,26 @@
+-- Applied after postgres.sql on databases with the TimescaleDB extension, and read from when
+-- POSTGRES_TIMESCALEDB is set.
+CREATE EXTENSION IF NOT EXISTS timescaledb;
+
+SELECT create_hypertable('player_stats', 'recorded_at', if_not_exists => TRUE, migrate_data => TRUE);
+
+-- Not materialized_only, so that the buckets yet to be refreshed are aggregated on read.
+CREATE MATERIALIZED VIEW IF NOT EXISTS player_stats_hourly
+WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+SELECT
+    time_bucket(INTERVAL '1 hour', recorded_at) AS bucket,
+    player_uuid,
+    kind,
+    MAX(value) AS max_value,
+    MIN(value) AS min_value
+FROM player_stats
+GROUP BY bucket, player_uuid, kind
+WITH NO DATA;
+
+SELECT add_continuous_aggregate_policy(
+    'player_stats_hourly',
+    start_offset => INTERVAL '3 days',
+    end_offset => INTERVAL '1 hour',
+    schedule_interval => INTERVAL '1 hour',
+    if_not_exists => TRUE
+);
@@ -0,0 +1,137 @@
+//! Runs the `ClickHouse` repository against a disposable database, created on the server whose
+//! HTTP interface is at `READER_TEST_CLICKHOUSE_URL` (such as `http://127.0.0.1:8123`) and
+//! dropped afterwards. Ignored unless run with `--ignored`.
+
+mod repository_scenarios;
+
+use async_trait::async_trait;
+use clickhouse::Client;
+use repository_scenarios::History;
+use seichi_timed_stats_reader::domain::PlayerDataKind;
+use seichi_timed_stats_reader::infra_repository_impls::clickhouse::{
+    ClickHouseStatsHistoryRepository, SCHEMA,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Dropped with the guard, so that a failing test does not leave it behind.
+struct DisposableDatabase {
+    server: Client,
+    name: String,
+    pool: Client,
+}
+
+impl DisposableDatabase {
+    async fn create() -> Self {
+        let server_url = std::env::var("READER_TEST_CLICKHOUSE_URL")
+            .expect("READER_TEST_CLICKHOUSE_URL is not set");
+        let server = Client::default().with_url(server_url);
+        let name = format!(
+            "reader_test_{}_{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        );
+        server
+            .query(&format!("CREATE DATABASE {name}"))
+            .execute()
+            .await
+            .unwrap();
+
+        let pool = server.clone().with_database(&name);
+        let database = Self { server, name, pool };
+        database.pool.query(SCHEMA).execute().await.unwrap();
+
+        database
+    }
+}
+
+impl Drop for DisposableDatabase {
+    /// Dropped on a runtime of its own, as that of the test may be shutting down.
+    fn drop(&mut self) {
+        let (server, name) = (self.server.clone(), self.name.clone());
+        let dropped = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(
+                    server
+                        .query(&format!("DROP DATABASE IF EXISTS {name}"))
+                        .execute(),
+                )
+        })
+        .join();
+
+        if !matches!(dropped, Ok(Ok(()))) {
+            eprintln!("failed to drop the database {}", self.name);
+        }
+    }
+}
+
+#[async_trait]
+impl History for DisposableDatabase {
+    type Repository = ClickHouseStatsHistoryRepository;
+
+    async fn record(&self, player: &str, kind: PlayerDataKind, seconds: u64, value: u64) {
+        self.pool
+            .query(
+                "INSERT INTO player_stats (player_uuid, kind, recorded_at, value) \
+                 VALUES (toUUID(?), ?, toDateTime64(?, 3, 'UTC'), ?)",
+            )
+            .bind(player)
+            .bind(kind.as_str())
+            .bind(seconds)
+            .bind(value)
+            .execute()
+            .await
+            .unwrap();
+    }
+
+    async fn repository(&self) -> Self::Repository {
+        ClickHouseStatsHistoryRepository::new(self.pool.clone())
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a ClickHouse server at READER_TEST_CLICKHOUSE_URL"]
+async fn get_samples_of_a_player_and_kind_within_a_range() {
+    let database = DisposableDatabase::create().await;
+    repository_scenarios::get_samples_of_a_player_and_kind_within_a_range(&database).await;
+}
+
+#[tokio::test]
+#[ignore = "needs a ClickHouse server at READER_TEST_CLICKHOUSE_URL"]
+async fn stream_samples_of_a_kind_of_every_player() {
+    let database = DisposableDatabase::create().await;
+    repository_scenarios::stream_samples_of_a_kind_of_every_player(&database).await;
+}
+
+#[tokio::test]
+#[ignore = "needs a ClickHouse server at READER_TEST_CLICKHOUSE_URL"]
+async fn get_growth_of_every_player_within_a_range() {
+    let database = DisposableDatabase::create().await;
+    repository_scenarios::get_growth_of_every_player_within_a_range(&database).await;
+}
+
+#[tokio::test]
+#[ignore = "needs a ClickHouse server at READER_TEST_CLICKHOUSE_URL"]
+async fn get_top_gainers_of_a_kind_within_a_range() {
+    let database = DisposableDatabase::create().await;
+    repository_scenarios::get_top_gainers_of_a_kind_within_a_range(&database).await;
+}
+
+#[tokio::test]
+#[ignore = "needs a ClickHouse server at READER_TEST_CLICKHOUSE_URL"]
+async fn get_growth_and_top_gainers_across_hours() {
+    let database = DisposableDatabase::create().await;
+    repository_scenarios::get_growth_and_top_gainers_across_hours(&database).await;
+}
+
+#[tokio::test]
+#[ignore = "needs a ClickHouse server at READER_TEST_CLICKHOUSE_URL"]
+async fn read_back_recorded_snapshots() {
+    let database = DisposableDatabase::create().await;
+    repository_scenarios::read_back_recorded_snapshots(&database).await;
+}
@@ -0,0 +1,336 @@
+//! Runs the HTTP API against the in-memory repository, without binding any port.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use seichi_timed_stats_config::ConfigSources;
+use seichi_timed_stats_reader::app::build_router;
+use seichi_timed_stats_reader::domain::{
+    PlayerDataKind, PlayerStatsGrowth, PlayerUuid, StatsHistoryRepository, StatsSample, TimeRange,
+};
+use seichi_timed_stats_reader::infra_axum_handlers::SharedAppState;
+use seichi_timed_stats_reader::infra_repository_impls::in_memory::InMemoryStatsHistoryRepository;
+use seichi_timed_stats_reader::use_cases::config::{LeaderboardConfig, PaginationConfig};
+use seichi_timed_stats_reader::use_cases::LeaderboardCache;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tower::ServiceExt;
+
+const PLAYER: &str = "00000000-0000-4000-8000-000000000001";
+
+async fn get(uri: &str) -> (StatusCode, hyper::body::Bytes) {
+    get_paged_by(
+        PaginationConfig::load(&ConfigSources::default()).unwrap(),
+        uri,
+    )
+    .await
+}
+
+async fn get_paged_by(
+    pagination_config: PaginationConfig,
+    uri: &str,
+) -> (StatusCode, hyper::body::Bytes) {
+    let repository =
+        InMemoryStatsHistoryRepository::from_json(include_str!("fixtures/history.json")).unwrap();
+
+    get_from(Arc::new(repository), pagination_config, uri).await
+}
+
+async fn get_from(
+    repository: Arc<dyn StatsHistoryRepository>,
+    pagination_config: PaginationConfig,
+    uri: &str,
+) -> (StatusCode, hyper::body::Bytes) {
+    let router = build_router(SharedAppState {
+        repository,
+        leaderboard_cache: Arc::new(LeaderboardCache::default()),
+        leaderboard_config: LeaderboardConfig::load(&ConfigSources::default()).unwrap(),
+        pagination_config,
+        readiness_timeout: Duration::from_millis(100),
+    });
+
+    let response = router
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+
+    (
+        status,
+        hyper::body::to_bytes(response.into_body()).await.unwrap(),
+    )
+}
+
+/// Posts `query` to the GraphQL endpoint and gives the messages of the errors, if any.
+async fn post_graphql(query: &str) -> Vec<String> {
+    let router = build_router(SharedAppState {
+        repository: Arc::new(
+            InMemoryStatsHistoryRepository::from_json(include_str!("fixtures/history.json"))
+                .unwrap(),
+        ),
+        leaderboard_cache: Arc::new(LeaderboardCache::default()),
+        leaderboard_config: LeaderboardConfig::load(&ConfigSources::default()).unwrap(),
+        pagination_config: PaginationConfig::load(&ConfigSources::default()).unwrap(),
+        readiness_timeout: Duration::from_millis(100),
+    });
+
+    let response = router
+        .oneshot(
+            Request::post("/graphql")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "query": query }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body = serde_json::from_slice::<serde_json::Value>(&body).unwrap();
+
+    body["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|error| error["message"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn get_the_history_as_json() {
+    let (status, body) = get(&format!(
+        "/api/v1/history/{PLAYER}/break_count?from=1970-01-01T00:21:40Z&to=1970-01-01T00:26:40Z"
+    ))
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+        serde_json::json!({
+            "samples": [
+                { "recorded_at": "1970-01-01T00:21:40.000Z", "value": 20 },
+                { "recorded_at": "1970-01-01T00:26:40.000Z", "value": 30 },
+            ],
+            "next_cursor": null,
+        })
+    );
+}
+
+#[tokio::test]
+async fn get_the_growth_as_json() {
+    let (status, body) = get(&format!(
+        "/api/v1/growth?player={PLAYER}&from=1970-01-01T00:21:40Z&to=1970-01-01T00:31:40Z"
+    ))
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+        serde_json::json!({
+            "players": [
+                { "uuid": PLAYER, "growth": { "break_count": 20, "build_count": 0 } },
+            ],
+        })
+    );
+}
+
+#[tokio::test]
+async fn export_the_history_as_csv_across_pages() {
+    let (status, body) = get_paged_by(
+        PaginationConfig {
+            history_default_page_size: 1,
+            history_max_page_size: 1,
+        },
+        &format!("/export/history.csv?uuid={PLAYER}&kind=break_count&to=1970-01-01T00:26:40Z"),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        "recorded_at,value\n\
+         1970-01-01T00:16:40.000Z,10\n\
+         1970-01-01T00:21:40.000Z,20\n\
+         1970-01-01T00:26:40.000Z,30\n"
+    );
+}
+
+#[tokio::test]
+async fn reject_an_unknown_kind() {
+    let (status, _) = get(&format!("/api/v1/history/{PLAYER}/mined_blocks")).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn get_the_history_in_buckets_spanning_several_fetches() {
+    let repository = InMemoryStatsHistoryRepository::new((0..2_500).map(|seconds| {
+        (
+            PlayerUuid::parse(PLAYER).unwrap(),
+            PlayerDataKind::BreakCount,
+            StatsSample {
+                recorded_at: UNIX_EPOCH + Duration::from_secs(seconds),
+                value: seconds,
+            },
+        )
+    }));
+
+    let (status, body) = get_from(
+        Arc::new(repository),
+        PaginationConfig::load(&ConfigSources::default()).unwrap(),
+        &format!(
+            "/api/v1/history/{PLAYER}/break_count?from=1970-01-01T00:00:00Z\
+             &to=1970-01-01T00:41:39Z&resolution=10m&aggregation=delta"
+        ),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+        serde_json::json!({
+            "samples": [
+                { "recorded_at": "1970-01-01T00:00:00.000Z", "value": 599 },
+                { "recorded_at": "1970-01-01T00:10:00.000Z", "value": 600 },
+                { "recorded_at": "1970-01-01T00:20:00.000Z", "value": 600 },
+                { "recorded_at": "1970-01-01T00:30:00.000Z", "value": 600 },
+                { "recorded_at": "1970-01-01T00:40:00.000Z", "value": 100 },
+            ],
+            "next_cursor": null,
+        })
+    );
+}
+
+#[tokio::test]
+async fn reject_pages_of_buckets_ending_beyond_the_representable_time() {
+    let (status, _) = get(&format!(
+        "/api/v1/history/{PLAYER}/break_count?resolution=1000000000000000s&page_size=10000"
+    ))
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn answer_a_graphql_query_for_many_players() {
+    let uuids = vec![PLAYER; 100];
+    let errors = post_graphql(&format!(
+        "{{ players(uuids: {uuids:?}) {{ \
+             breaks: history(kind: BREAK_COUNT) {{ samples {{ recordedAt value }} }} \
+             builds: history(kind: BUILD_COUNT) {{ samples {{ recordedAt value }} }} \
+         }} }}"
+    ))
+    .await;
+
+    assert_eq!(errors, Vec::<String>::new());
+}
+
+#[tokio::test]
+async fn reject_a_graphql_query_reading_the_database_too_many_times() {
+    let uuids = vec![PLAYER; 100];
+    let errors = post_graphql(&format!(
+        "{{ players(uuids: {uuids:?}) {{ \
+             breaks: history(kind: BREAK_COUNT) {{ samples {{ value }} }} \
+             builds: history(kind: BUILD_COUNT) {{ samples {{ value }} }} \
+             votes: history(kind: VOTE_COUNT) {{ samples {{ value }} }} \
+             logins: history(kind: LOGIN_COUNT) {{ samples {{ value }} }} \
+         }} }}"
+    ))
+    .await;
+
+    assert_eq!(errors, vec!["Query is too complex.".to_string()]);
+}
+
+#[tokio::test]
+async fn reject_a_graphql_query_with_too_many_aliases() {
+    let fields = (0..31)
+        .map(|index| format!("player{index}: player(uuid: \"{PLAYER}\") {{ uuid }}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let errors = post_graphql(&format!("{{ {fields} }}")).await;
+
+    assert_eq!(
+        errors,
+        vec!["Expected at most 30 aliases, got 31".to_string()]
+    );
+}
+
+/// Repository whose database never answers.
+#[derive(Debug)]
+struct UnresponsiveRepository;
+
+#[async_trait::async_trait]
+impl StatsHistoryRepository for UnresponsiveRepository {
+    async fn get_samples(
+        &self,
+        _: PlayerUuid,
+        _: PlayerDataKind,
+        _: TimeRange,
+        _: Option<u32>,
+    ) -> anyhow::Result<Vec<StatsSample>> {
+        std::future::pending().await
+    }
+
+    fn stream_samples_of_every_player(
+        &self,
+        _: PlayerDataKind,
+        _: TimeRange,
+    ) -> BoxStream<'_, anyhow::Result<(PlayerUuid, StatsSample)>> {
+        stream::pending().boxed()
+    }
+
+    async fn get_growth(
+        &self,
+        _: Option<PlayerUuid>,
+        _: TimeRange,
+    ) -> anyhow::Result<Vec<PlayerStatsGrowth>> {
+        std::future::pending().await
+    }
+
+    async fn get_top_gainers(
+        &self,
+        _: PlayerDataKind,
+        _: TimeRange,
+        _: u32,
+    ) -> anyhow::Result<Vec<(PlayerUuid, u64)>> {
+        std::future::pending().await
+    }
+
+    async fn get_latest_recorded_at(&self) -> anyhow::Result<Option<SystemTime>> {
+        std::future::pending().await
+    }
+
+    async fn check_readiness(&self) -> anyhow::Result<()> {
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn be_live_and_ready_with_a_reachable_database() {
+    let (liveness, _) = get("/healthz").await;
+    let (readiness, body) = get("/readyz").await;
+
+    assert_eq!(liveness, StatusCode::OK);
+    assert_eq!(readiness, StatusCode::OK);
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+        serde_json::json!({ "ready": true })
+    );
+}
+
+#[tokio::test]
+async fn be_live_but_not_ready_when_the_database_does_not_answer_in_time() {
+    let repository = Arc::new(UnresponsiveRepository);
+    let pagination_config = || PaginationConfig::load(&ConfigSources::default()).unwrap();
+
+    let (liveness, _) = get_from(repository.clone(), pagination_config(), "/healthz").await;
+    let (readiness, body) = get_from(repository, pagination_config(), "/readyz").await;
+
+    assert_eq!(liveness, StatusCode::OK);
+    assert_eq!(readiness, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+        serde_json::json!({ "ready": false })
+    );
+}
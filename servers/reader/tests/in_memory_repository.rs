@@ -0,0 +1,136 @@
+//! Runs the in-memory repository against the shared scenarios, and against the fixtures under
+//! `tests/fixtures`, which hold the same history in JSON and in CSV.
+
+mod repository_scenarios;
+
+use async_trait::async_trait;
+use repository_scenarios::History;
+use seichi_timed_stats_reader::domain::{
+    PlayerDataKind, PlayerUuid, StatsHistoryRepository, StatsSample, TimeRange,
+};
+use seichi_timed_stats_reader::infra_repository_impls::in_memory::InMemoryStatsHistoryRepository;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PLAYER: &str = "00000000-0000-4000-8000-000000000001";
+const OTHER_PLAYER: &str = "00000000-0000-4000-8000-000000000002";
+
+fn at(seconds: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+async fn fixture(name: &str) -> InMemoryStatsHistoryRepository {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+
+    InMemoryStatsHistoryRepository::load_fixtures(&[path])
+        .await
+        .unwrap()
+}
+
+async fn fixtures() -> [InMemoryStatsHistoryRepository; 2] {
+    [fixture("history.json").await, fixture("history.csv").await]
+}
+
+#[derive(Default)]
+struct Samples(Mutex<Vec<(PlayerUuid, PlayerDataKind, StatsSample)>>);
+
+#[async_trait]
+impl History for Samples {
+    type Repository = InMemoryStatsHistoryRepository;
+
+    async fn record(&self, player: &str, kind: PlayerDataKind, seconds: u64, value: u64) {
+        self.0.lock().unwrap().push((
+            PlayerUuid::parse(player).unwrap(),
+            kind,
+            StatsSample {
+                recorded_at: at(seconds),
+                value,
+            },
+        ));
+    }
+
+    async fn repository(&self) -> Self::Repository {
+        InMemoryStatsHistoryRepository::new(self.0.lock().unwrap().clone())
+    }
+}
+
+#[tokio::test]
+async fn get_samples_of_a_player_and_kind_within_a_range() {
+    repository_scenarios::get_samples_of_a_player_and_kind_within_a_range(&Samples::default())
+        .await;
+}
+
+#[tokio::test]
+async fn stream_samples_of_a_kind_of_every_player() {
+    repository_scenarios::stream_samples_of_a_kind_of_every_player(&Samples::default()).await;
+}
+
+#[tokio::test]
+async fn get_growth_of_every_player_within_a_range() {
+    repository_scenarios::get_growth_of_every_player_within_a_range(&Samples::default()).await;
+}
+
+#[tokio::test]
+async fn get_top_gainers_of_a_kind_within_a_range() {
+    repository_scenarios::get_top_gainers_of_a_kind_within_a_range(&Samples::default()).await;
+}
+
+#[tokio::test]
+async fn get_growth_and_top_gainers_across_hours() {
+    repository_scenarios::get_growth_and_top_gainers_across_hours(&Samples::default()).await;
+}
+
+#[tokio::test]
+async fn load_the_same_history_from_json_and_csv() {
+    for repository in fixtures().await {
+        assert_eq!(
+            repository
+                .get_samples(
+                    PlayerUuid::parse(PLAYER).unwrap(),
+                    PlayerDataKind::BreakCount,
+                    TimeRange::new(at(0), at(2_000)).unwrap(),
+                    None,
+                )
+                .await
+                .unwrap(),
+            [(1_000, 10), (1_300, 20), (1_600, 30), (1_900, 40)]
+                .map(|(seconds, value)| StatsSample {
+                    recorded_at: at(seconds),
+                    value,
+                })
+                .to_vec()
+        );
+    }
+}
+
+#[tokio::test]
+async fn shift_the_history_to_end_at_a_time() {
+    let [repository, _] = fixtures().await;
+    let repository = repository.shifted_to_end_at(at(10_000));
+
+    assert_eq!(
+        repository.get_latest_recorded_at().await.unwrap(),
+        Some(at(10_000))
+    );
+    assert_eq!(
+        repository
+            .get_samples(
+                PlayerUuid::parse(OTHER_PLAYER).unwrap(),
+                PlayerDataKind::BreakCount,
+                TimeRange::new(at(0), at(10_000)).unwrap(),
+                None,
+            )
+            .await
+            .unwrap(),
+        vec![
+            StatsSample {
+                recorded_at: at(9_400),
+                value: 7,
+            },
+            StatsSample {
+                recorded_at: at(10_000),
+                value: 9,
+            },
+        ]
+    );
+}
@@ -0,0 +1,75 @@
+//! Exports the history of the fixtures under `tests/fixtures` to Parquet and reads it back.
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{TimestampMillisecondType, UInt64Type};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use seichi_timed_stats_reader::domain::{PlayerDataKind, TimeRange};
+use seichi_timed_stats_reader::infra_parquet_export::ParquetHistoryWriter;
+use seichi_timed_stats_reader::infra_repository_impls::in_memory::InMemoryStatsHistoryRepository;
+use seichi_timed_stats_reader::use_cases::ExportHistoryUseCase;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+const PLAYER: &str = "00000000-0000-4000-8000-000000000001";
+const OTHER_PLAYER: &str = "00000000-0000-4000-8000-000000000002";
+
+#[tokio::test]
+async fn export_the_samples_of_some_kinds_within_a_range() {
+    let repository =
+        InMemoryStatsHistoryRepository::from_csv(include_str!("fixtures/history.csv")).unwrap();
+    let use_case = ExportHistoryUseCase {
+        repository: Arc::new(repository),
+    };
+    let range = TimeRange::new(
+        UNIX_EPOCH + Duration::from_secs(1_300),
+        UNIX_EPOCH + Duration::from_secs(1_900),
+    )
+    .unwrap();
+
+    let mut writer = ParquetHistoryWriter::new(Vec::new()).unwrap();
+    let exported = use_case
+        .export_history(
+            range,
+            &[PlayerDataKind::BreakCount],
+            |player, kind, samples| writer.write_samples(player, kind, samples),
+        )
+        .await
+        .unwrap();
+    let file = bytes::Bytes::from(writer.finish().unwrap());
+
+    let batches = ParquetRecordBatchReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    // So few samples are written in a single row group
+    let [rows] = batches.as_slice() else {
+        panic!("Expected a single batch, got {}", batches.len());
+    };
+    let column = |name: &str| rows.column_by_name(name).unwrap().clone();
+
+    assert_eq!(exported, 5);
+    assert_eq!(
+        column("player_uuid")
+            .as_string::<i32>()
+            .iter()
+            .flatten()
+            .collect::<Vec<_>>(),
+        [PLAYER, PLAYER, PLAYER, OTHER_PLAYER, OTHER_PLAYER]
+    );
+    assert!(column("kind")
+        .as_string::<i32>()
+        .iter()
+        .all(|kind| kind == Some("break_count")));
+    assert_eq!(
+        column("recorded_at")
+            .as_primitive::<TimestampMillisecondType>()
+            .values(),
+        &[1_300_000, 1_600_000, 1_900_000, 1_300_000, 1_900_000]
+    );
+    assert_eq!(
+        column("value").as_primitive::<UInt64Type>().values(),
+        &[20, 30, 40, 7, 9]
+    );
+}
@@ -0,0 +1,144 @@
+//! Records snapshots scraped from a stand-in for the translator into an in-memory `SQLite`
+//! database.
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use seichi_timed_stats_reader::domain::{
+    PlayerDataKind, PlayerUuid, SnapshotValue, StatsHistoryRecorder, StatsHistoryRepository,
+    StatsSample, StatsSnapshotSource, TimeRange,
+};
+use seichi_timed_stats_reader::infra_repository_impls::sqlite::SqliteStatsHistoryRepository;
+use seichi_timed_stats_reader::infra_translator_metrics::{
+    parse_player_data, TranslatorMetricsSource,
+};
+use seichi_timed_stats_reader::use_cases::RecordSnapshotUseCase;
+use sqlx::sqlite::SqlitePoolOptions;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+const PLAYER: &str = "00000000-0000-4000-8000-000000000001";
+const OTHER_PLAYER: &str = "00000000-0000-4000-8000-000000000002";
+
+const EXPOSITION: &str = r#"# HELP player_data Player metrics, partitioned by uuid and kind
+# TYPE player_data gauge
+player_data{uuid="00000000-0000-4000-8000-000000000001",kind="break_count",server="s1",season="a \"quoted\" season"} 10
+player_data{uuid="00000000-0000-4000-8000-000000000001",kind="break_count",server="s2",season="a \"quoted\" season"} 5
+player_data{uuid="00000000-0000-4000-8000-000000000001",kind="build_count",server="s1",season="a \"quoted\" season"} 3
+player_data{uuid="00000000-0000-4000-8000-000000000002",kind="break_count"} 7
+player_data{uuid="00000000-0000-4000-8000-000000000002",kind="unknown_count"} 1
+player_data{uuid="Zm9vYmFy",kind="break_count"} 1
+# HELP player_data_delta Change of player metrics since the previous refresh, partitioned by uuid and kind
+# TYPE player_data_delta gauge
+player_data_delta{uuid="00000000-0000-4000-8000-000000000002",kind="break_count"} 2
+"#;
+
+fn value(uuid: &str, kind: PlayerDataKind, value: u64) -> SnapshotValue {
+    SnapshotValue {
+        player: PlayerUuid::parse(uuid).unwrap(),
+        kind,
+        value,
+    }
+}
+
+#[test]
+fn parse_the_player_data_of_an_exposition() {
+    assert_eq!(
+        parse_player_data(EXPOSITION),
+        vec![
+            value(PLAYER, PlayerDataKind::BreakCount, 15),
+            value(PLAYER, PlayerDataKind::BuildCount, 3),
+            value(OTHER_PLAYER, PlayerDataKind::BreakCount, 7),
+        ]
+    );
+}
+
+/// Serves [`EXPOSITION`] at `/metrics` to requests bearing `token`.
+async fn serve_translator(token: &'static str) -> SocketAddr {
+    let router = Router::new().route(
+        "/metrics",
+        get(move |headers: HeaderMap| async move {
+            let authorized = headers
+                .get("authorization")
+                .is_some_and(|value| value.as_bytes() == format!("Bearer {token}").as_bytes());
+            if authorized {
+                (StatusCode::OK, EXPOSITION)
+            } else {
+                (StatusCode::UNAUTHORIZED, "")
+            }
+        }),
+    );
+    let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+        .serve(router.into_make_service());
+    let address = server.local_addr();
+    tokio::spawn(server);
+
+    address
+}
+
+#[tokio::test]
+async fn record_a_snapshot_scraped_from_the_translator() {
+    let address = serve_translator("token").await;
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let repository = SqliteStatsHistoryRepository::new(pool);
+    repository.migrate().await.unwrap();
+
+    let use_case = RecordSnapshotUseCase {
+        source: Arc::new(
+            TranslatorMetricsSource::new(
+                &format!("http://{address}/metrics"),
+                Some("Bearer token"),
+                Duration::from_secs(5),
+            )
+            .unwrap(),
+        ),
+        recorder: Arc::new(repository.clone()),
+    };
+    let recorded_at = UNIX_EPOCH + Duration::from_millis(1_000_500);
+    let count = use_case
+        .record_snapshot(recorded_at + Duration::from_micros(700))
+        .await;
+
+    assert_eq!(count.unwrap(), 3);
+    assert_eq!(
+        repository.get_latest_recorded_at().await.unwrap(),
+        Some(recorded_at)
+    );
+    assert_eq!(
+        repository
+            .get_samples(
+                PlayerUuid::parse(PLAYER).unwrap(),
+                PlayerDataKind::BreakCount,
+                TimeRange::new(UNIX_EPOCH, recorded_at).unwrap(),
+                None,
+            )
+            .await
+            .unwrap(),
+        vec![StatsSample {
+            recorded_at,
+            value: 15,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn fail_to_scrape_without_the_authorization_of_the_translator() {
+    let address = serve_translator("token").await;
+    let source = TranslatorMetricsSource::new(
+        &format!("http://{address}/metrics"),
+        Some("Bearer another token"),
+        Duration::from_secs(5),
+    )
+    .unwrap();
+
+    let error = source.fetch_snapshot().await.unwrap_err();
+
+    assert!(error.to_string().contains("401"), "{error}");
+}
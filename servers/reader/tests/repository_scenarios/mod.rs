@@ -0,0 +1,348 @@
+//! Scenarios every repository is run against. Each one records its own history through a
+//! [`History`] and then asks the repository built on it, so that the implementations agree on the
+//! same expectations.
+
+// Not every repository can run every scenario
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use seichi_timed_stats_reader::domain::{
+    PlayerDataKind, PlayerStatsGrowth, PlayerUuid, SnapshotValue, StatsHistoryRecorder,
+    StatsHistoryRepository, StatsSample, TimeRange,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PLAYER: &str = "00000000-0000-4000-8000-000000000001";
+const OTHER_PLAYER: &str = "00000000-0000-4000-8000-000000000002";
+
+/// Where a scenario records the history that the repository under test reads.
+#[async_trait]
+pub trait History: Sync {
+    type Repository: StatsHistoryRepository;
+
+    async fn record(&self, player: &str, kind: PlayerDataKind, seconds: u64, value: u64);
+
+    /// The repository reading what has been recorded so far.
+    async fn repository(&self) -> Self::Repository;
+}
+
+fn at(seconds: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+fn player(uuid: &str) -> PlayerUuid {
+    PlayerUuid::parse(uuid).unwrap()
+}
+
+/// The kinds of each growth are sorted, as the repositories group them in no particular order.
+fn sorted(mut growth: Vec<PlayerStatsGrowth>) -> Vec<PlayerStatsGrowth> {
+    for growth in &mut growth {
+        growth.growth.sort_by_key(|(kind, _)| kind.as_str());
+    }
+
+    growth
+}
+
+pub async fn get_samples_of_a_player_and_kind_within_a_range(history: &impl History) {
+    for (seconds, value) in [(1_000, 10), (1_300, 20), (1_600, 30), (1_900, 40)] {
+        history
+            .record(PLAYER, PlayerDataKind::BreakCount, seconds, value)
+            .await;
+    }
+    history
+        .record(PLAYER, PlayerDataKind::BuildCount, 1_300, 5)
+        .await;
+    history
+        .record(OTHER_PLAYER, PlayerDataKind::BreakCount, 1_300, 7)
+        .await;
+
+    let repository = history.repository().await;
+    let samples = repository
+        .get_samples(
+            player(PLAYER),
+            PlayerDataKind::BreakCount,
+            TimeRange::new(at(1_300), at(1_600)).unwrap(),
+            None,
+        )
+        .await;
+    let first_sample = repository
+        .get_samples(
+            player(PLAYER),
+            PlayerDataKind::BreakCount,
+            TimeRange::new(at(1_300), at(1_900)).unwrap(),
+            Some(1),
+        )
+        .await;
+
+    assert_eq!(
+        samples.unwrap(),
+        vec![
+            StatsSample {
+                recorded_at: at(1_300),
+                value: 20,
+            },
+            StatsSample {
+                recorded_at: at(1_600),
+                value: 30,
+            },
+        ]
+    );
+    assert_eq!(
+        first_sample.unwrap(),
+        vec![StatsSample {
+            recorded_at: at(1_300),
+            value: 20,
+        }]
+    );
+}
+
+pub async fn stream_samples_of_a_kind_of_every_player(history: &impl History) {
+    history
+        .record(OTHER_PLAYER, PlayerDataKind::BreakCount, 1_300, 7)
+        .await;
+    for (seconds, value) in [(1_000, 10), (1_600, 30), (1_300, 20)] {
+        history
+            .record(PLAYER, PlayerDataKind::BreakCount, seconds, value)
+            .await;
+    }
+    history
+        .record(PLAYER, PlayerDataKind::BuildCount, 1_300, 5)
+        .await;
+
+    let repository = history.repository().await;
+    let samples = repository
+        .stream_samples_of_every_player(
+            PlayerDataKind::BreakCount,
+            TimeRange::new(at(1_300), at(1_900)).unwrap(),
+        )
+        .try_collect::<Vec<_>>()
+        .await;
+
+    let sample = |seconds, value| StatsSample {
+        recorded_at: at(seconds),
+        value,
+    };
+    assert_eq!(
+        samples.unwrap(),
+        vec![
+            (player(PLAYER), sample(1_300, 20)),
+            (player(PLAYER), sample(1_600, 30)),
+            (player(OTHER_PLAYER), sample(1_300, 7)),
+        ]
+    );
+}
+
+pub async fn get_growth_of_every_player_within_a_range(history: &impl History) {
+    for (seconds, value) in [(1_000, 10), (1_300, 20), (1_600, 30), (1_900, 40)] {
+        history
+            .record(PLAYER, PlayerDataKind::BreakCount, seconds, value)
+            .await;
+    }
+    history
+        .record(PLAYER, PlayerDataKind::BuildCount, 1_300, 5)
+        .await;
+    history
+        .record(OTHER_PLAYER, PlayerDataKind::BreakCount, 1_300, 7)
+        .await;
+    history
+        .record(OTHER_PLAYER, PlayerDataKind::BreakCount, 1_900, 9)
+        .await;
+
+    let repository = history.repository().await;
+    let range = TimeRange::new(at(1_300), at(1_600)).unwrap();
+    let every_player = repository.get_growth(None, range).await;
+    let one_player = repository
+        .get_growth(Some(player(OTHER_PLAYER)), range)
+        .await;
+
+    assert_eq!(
+        sorted(every_player.unwrap()),
+        vec![
+            PlayerStatsGrowth {
+                player: player(PLAYER),
+                growth: vec![
+                    (PlayerDataKind::BreakCount, 10),
+                    (PlayerDataKind::BuildCount, 0),
+                ],
+            },
+            PlayerStatsGrowth {
+                player: player(OTHER_PLAYER),
+                growth: vec![(PlayerDataKind::BreakCount, 0)],
+            },
+        ]
+    );
+    assert_eq!(
+        one_player.unwrap(),
+        vec![PlayerStatsGrowth {
+            player: player(OTHER_PLAYER),
+            growth: vec![(PlayerDataKind::BreakCount, 0)],
+        }]
+    );
+}
+
+pub async fn get_top_gainers_of_a_kind_within_a_range(history: &impl History) {
+    for (uuid, seconds, value) in [
+        (PLAYER, 1_300, 10),
+        (PLAYER, 1_600, 15),
+        (OTHER_PLAYER, 1_300, 100),
+        (OTHER_PLAYER, 1_600, 130),
+        (OTHER_PLAYER, 1_900, 1_000),
+    ] {
+        history
+            .record(uuid, PlayerDataKind::BreakCount, seconds, value)
+            .await;
+    }
+    history
+        .record(PLAYER, PlayerDataKind::BuildCount, 1_300, 0)
+        .await;
+    history
+        .record(PLAYER, PlayerDataKind::BuildCount, 1_600, 500)
+        .await;
+
+    let repository = history.repository().await;
+    let range = TimeRange::new(at(1_300), at(1_600)).unwrap();
+    let gainers = repository
+        .get_top_gainers(PlayerDataKind::BreakCount, range, 10)
+        .await;
+    let top_gainer = repository
+        .get_top_gainers(PlayerDataKind::BreakCount, range, 1)
+        .await;
+
+    assert_eq!(
+        gainers.unwrap(),
+        vec![(player(OTHER_PLAYER), 30), (player(PLAYER), 5)]
+    );
+    assert_eq!(top_gainer.unwrap(), vec![(player(OTHER_PLAYER), 30)]);
+}
+
+/// Spans whole hours between partial ones at either end, which the repositories reading an
+/// hourly aggregate answer from two sources.
+pub async fn get_growth_and_top_gainers_across_hours(history: &impl History) {
+    for (uuid, seconds, value) in [
+        (PLAYER, 1_000, 10),
+        (PLAYER, 3_700, 20),
+        (PLAYER, 7_300, 35),
+        (PLAYER, 10_900, 50),
+        (PLAYER, 11_000, 70),
+        (OTHER_PLAYER, 3_600, 5),
+        (OTHER_PLAYER, 10_800, 6),
+    ] {
+        history
+            .record(uuid, PlayerDataKind::BreakCount, seconds, value)
+            .await;
+    }
+
+    let repository = history.repository().await;
+    let range = TimeRange::new(at(1_300), at(10_900)).unwrap();
+    let every_player = repository.get_growth(None, range).await;
+    let one_player = repository.get_growth(Some(player(PLAYER)), range).await;
+    let gainers = repository
+        .get_top_gainers(PlayerDataKind::BreakCount, range, 10)
+        .await;
+
+    assert_eq!(
+        every_player.unwrap(),
+        vec![
+            PlayerStatsGrowth {
+                player: player(PLAYER),
+                growth: vec![(PlayerDataKind::BreakCount, 30)],
+            },
+            PlayerStatsGrowth {
+                player: player(OTHER_PLAYER),
+                growth: vec![(PlayerDataKind::BreakCount, 1)],
+            },
+        ]
+    );
+    assert_eq!(
+        one_player.unwrap(),
+        vec![PlayerStatsGrowth {
+            player: player(PLAYER),
+            growth: vec![(PlayerDataKind::BreakCount, 30)],
+        }]
+    );
+    assert_eq!(
+        gainers.unwrap(),
+        vec![(player(PLAYER), 30), (player(OTHER_PLAYER), 1)]
+    );
+}
+
+/// Records snapshots through the repository itself, for the repositories of the databases that
+/// the recorder writes into.
+pub async fn read_back_recorded_snapshots<H>(history: &H)
+where
+    H: History,
+    H::Repository: StatsHistoryRecorder,
+{
+    let value = |uuid, kind, value| SnapshotValue {
+        player: player(uuid),
+        kind,
+        value,
+    };
+    let repository = history.repository().await;
+    repository.migrate().await.unwrap();
+
+    repository
+        .record_snapshot(
+            at(1_000),
+            &[
+                value(PLAYER, PlayerDataKind::BreakCount, 10),
+                value(PLAYER, PlayerDataKind::BuildCount, 5),
+                value(OTHER_PLAYER, PlayerDataKind::BreakCount, 7),
+            ],
+        )
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        repository
+            .record_snapshot(at(1_300), &[value(PLAYER, PlayerDataKind::BreakCount, 20)])
+            .await
+            .unwrap();
+    }
+
+    let samples = repository
+        .get_samples(
+            player(PLAYER),
+            PlayerDataKind::BreakCount,
+            TimeRange::new(at(0), at(2_000)).unwrap(),
+            None,
+        )
+        .await;
+    let growth = repository
+        .get_growth(None, TimeRange::new(at(1_000), at(1_300)).unwrap())
+        .await;
+
+    assert_eq!(
+        samples.unwrap(),
+        vec![
+            StatsSample {
+                recorded_at: at(1_000),
+                value: 10,
+            },
+            StatsSample {
+                recorded_at: at(1_300),
+                value: 20,
+            },
+        ]
+    );
+    assert_eq!(
+        sorted(growth.unwrap()),
+        vec![
+            PlayerStatsGrowth {
+                player: player(PLAYER),
+                growth: vec![
+                    (PlayerDataKind::BreakCount, 10),
+                    (PlayerDataKind::BuildCount, 0),
+                ],
+            },
+            PlayerStatsGrowth {
+                player: player(OTHER_PLAYER),
+                growth: vec![(PlayerDataKind::BreakCount, 0)],
+            },
+        ]
+    );
+    assert_eq!(
+        repository.get_latest_recorded_at().await.unwrap(),
+        Some(at(1_300))
+    );
+}
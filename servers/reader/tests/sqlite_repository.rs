@@ -0,0 +1,88 @@
+//! Runs the `SQLite` repository against an in-memory database, needing no database server.
+
+mod repository_scenarios;
+
+use async_trait::async_trait;
+use repository_scenarios::History;
+use seichi_timed_stats_reader::domain::{PlayerDataKind, PlayerUuid};
+use seichi_timed_stats_reader::infra_repository_impls::sqlite::{
+    SqliteStatsHistoryRepository, SCHEMA,
+};
+use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use sqlx::Executor;
+
+/// A single connection, which the in-memory database lives as long as.
+struct InMemoryDatabase(SqlitePool);
+
+impl InMemoryDatabase {
+    async fn create() -> Self {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        pool.execute(SCHEMA).await.unwrap();
+
+        Self(pool)
+    }
+}
+
+#[async_trait]
+impl History for InMemoryDatabase {
+    type Repository = SqliteStatsHistoryRepository;
+
+    async fn record(&self, player: &str, kind: PlayerDataKind, seconds: u64, value: u64) {
+        sqlx::query(
+            "INSERT INTO player_stats (player_uuid, kind, recorded_at, value) VALUES (?, ?, ?, ?)",
+        )
+        .bind(PlayerUuid::parse(player).unwrap().as_bytes().as_slice())
+        .bind(kind.as_str())
+        .bind(i64::try_from(seconds * 1_000).unwrap())
+        .bind(i64::try_from(value).unwrap())
+        .execute(&self.0)
+        .await
+        .unwrap();
+    }
+
+    async fn repository(&self) -> Self::Repository {
+        SqliteStatsHistoryRepository::new(self.0.clone())
+    }
+}
+
+#[tokio::test]
+async fn get_samples_of_a_player_and_kind_within_a_range() {
+    let database = InMemoryDatabase::create().await;
+    repository_scenarios::get_samples_of_a_player_and_kind_within_a_range(&database).await;
+}
+
+#[tokio::test]
+async fn stream_samples_of_a_kind_of_every_player() {
+    let database = InMemoryDatabase::create().await;
+    repository_scenarios::stream_samples_of_a_kind_of_every_player(&database).await;
+}
+
+#[tokio::test]
+async fn get_growth_of_every_player_within_a_range() {
+    let database = InMemoryDatabase::create().await;
+    repository_scenarios::get_growth_of_every_player_within_a_range(&database).await;
+}
+
+#[tokio::test]
+async fn get_top_gainers_of_a_kind_within_a_range() {
+    let database = InMemoryDatabase::create().await;
+    repository_scenarios::get_top_gainers_of_a_kind_within_a_range(&database).await;
+}
+
+#[tokio::test]
+async fn get_growth_and_top_gainers_across_hours() {
+    let database = InMemoryDatabase::create().await;
+    repository_scenarios::get_growth_and_top_gainers_across_hours(&database).await;
+}
+
+#[tokio::test]
+async fn read_back_recorded_snapshots() {
+    let database = InMemoryDatabase::create().await;
+    repository_scenarios::read_back_recorded_snapshots(&database).await;
+}
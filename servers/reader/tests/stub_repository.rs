@@ -0,0 +1,51 @@
+//! Runs the stub repository, whose fake history ends now.
+
+use seichi_timed_stats_reader::domain::{
+    PlayerDataKind, PlayerUuid, StatsHistoryRepository, TimeRange,
+};
+use seichi_timed_stats_reader::infra_repository_impls::stub::StubStatsHistoryRepository;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PLAYER: &str = "00000000-0000-4000-8000-000000000001";
+
+#[tokio::test]
+async fn get_no_samples_after_now() {
+    let repository = StubStatsHistoryRepository::new(1);
+    let now = SystemTime::now();
+
+    let samples = repository
+        .get_samples(
+            PlayerUuid::parse(PLAYER).unwrap(),
+            PlayerDataKind::BreakCount,
+            TimeRange::new(
+                now - Duration::from_secs(3_600),
+                UNIX_EPOCH + Duration::MAX / 2,
+            )
+            .unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert!((12..=13).contains(&samples.len()), "{}", samples.len());
+    assert!(samples.iter().all(|sample| sample.recorded_at <= now));
+}
+
+#[tokio::test]
+async fn get_samples_up_to_the_limit() {
+    let repository = StubStatsHistoryRepository::new(1);
+    let now = SystemTime::now();
+
+    let samples = repository
+        .get_samples(
+            PlayerUuid::parse(PLAYER).unwrap(),
+            PlayerDataKind::BreakCount,
+            TimeRange::new(UNIX_EPOCH, now).unwrap(),
+            Some(3),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(samples.len(), 3);
+    assert!(samples.is_sorted_by_key(|sample| sample.recorded_at));
+}
@@ -0,0 +1,44 @@
+#![deny(clippy::all, clippy::cargo)]
+#![warn(clippy::nursery, clippy::pedantic)]
+#![allow(clippy::cargo_common_metadata)]
+
+use clap::Parser;
+use seichi_timed_stats_reader::app as reader;
+use seichi_timed_stats_translator::app as translator;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    version,
+    about = "Translates the game data server into metrics, records them and serves their history"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Serve the player data of the game data server as Prometheus metrics, or run another
+    /// command of the translator.
+    ServeTranslator(translator::cli::Cli),
+    #[command(flatten)]
+    Reader(reader::cli::Command),
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    match Cli::parse().command {
+        Command::ServeTranslator(cli) => translator::run(&cli),
+        // The telemetry and the runtime of the reader are set up like those of the translator
+        Command::Reader(command) => {
+            let validated = command.clone();
+            let options = command.options().clone();
+            translator::bootstrap(
+                options.config.as_deref(),
+                options.overrides(),
+                move |config_sources| reader::validate_config(&validated, config_sources),
+                command.logs_to_stderr(),
+                |config_sources, _| async move { reader::run(command, &config_sources).await },
+            )
+        }
+    }
+}
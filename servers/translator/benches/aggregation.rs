@@ -0,0 +1,34 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use seichi_timed_stats_translator::infra_repository_impls::stub::StubPlayerDataRepository;
+use seichi_timed_stats_translator::use_cases::GetAllPlayerDataUseCase;
+use std::sync::Arc;
+
+const PLAYER_COUNTS: [u64; 3] = [10_000, 100_000, 500_000];
+
+fn bench_aggregation(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("get_all_known_aggregated_player_data");
+    group.sample_size(10);
+
+    for player_count in PLAYER_COUNTS {
+        let use_case = GetAllPlayerDataUseCase {
+            repository: Arc::new(StubPlayerDataRepository::new(player_count)),
+            derived_stats_config: Default::default(),
+            fetch_config: Default::default(),
+        };
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(player_count),
+            &use_case,
+            |b, use_case| {
+                b.to_async(&runtime)
+                    .iter(|| use_case.get_all_known_aggregated_player_data());
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_aggregation);
+criterion_main!(benches);
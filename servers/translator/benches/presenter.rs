@@ -0,0 +1,77 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use seichi_timed_stats_translator::domain::{PopulationStandings, UpstreamConnectionState};
+use seichi_timed_stats_translator::infra_axum_handlers::presenter::{
+    present_player_data_as_prometheus_metrics, PlayerRecordFragments, RecordPresentation,
+};
+use seichi_timed_stats_translator::infra_repository_impls::stub::StubPlayerDataRepository;
+use seichi_timed_stats_translator::use_cases::{
+    GetAllPlayerDataUseCase, GetPopulationSummaryUseCase, PlayerDataSnapshot,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+const PLAYER_COUNTS: [u64; 3] = [10_000, 100_000, 500_000];
+
+fn bench_presenter(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("present_player_data_as_prometheus_metrics");
+    group.sample_size(10);
+
+    for player_count in PLAYER_COUNTS {
+        let use_case = GetAllPlayerDataUseCase {
+            repository: Arc::new(StubPlayerDataRepository::new(player_count)),
+            derived_stats_config: Default::default(),
+            fetch_config: Default::default(),
+        };
+        let fetched = runtime
+            .block_on(use_case.get_all_known_aggregated_player_data())
+            .unwrap();
+        let snapshot = PlayerDataSnapshot {
+            id: 0,
+            summary: GetPopulationSummaryUseCase.get_population_summary(&fetched),
+            standings: Arc::new(PopulationStandings::of(&fetched)),
+            fetched: Arc::new(fetched),
+            deltas: None,
+            group_totals: None,
+            taken_at: Instant::now(),
+            restored: false,
+        };
+
+        group.bench_with_input(
+            BenchmarkId::new("full", player_count),
+            &snapshot,
+            |b, snapshot| {
+                b.iter(|| {
+                    present_player_data_as_prometheus_metrics(
+                        black_box(snapshot),
+                        UpstreamConnectionState::Ready,
+                        RecordPresentation::default(),
+                        &mut PlayerRecordFragments::default(),
+                    )
+                });
+            },
+        );
+
+        // Every player is unchanged since the previous render, so every record is reused
+        let mut record_fragments = PlayerRecordFragments::default();
+        group.bench_with_input(
+            BenchmarkId::new("unchanged", player_count),
+            &snapshot,
+            |b, snapshot| {
+                b.iter(|| {
+                    present_player_data_as_prometheus_metrics(
+                        black_box(snapshot),
+                        UpstreamConnectionState::Ready,
+                        RecordPresentation::default(),
+                        &mut record_fragments,
+                    )
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_presenter);
+criterion_main!(benches);
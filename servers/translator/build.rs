@@ -10,15 +10,46 @@ fn process_status(status: ExitStatus) {
     }
 }
 
+fn env_or(key: &str, default: impl FnOnce() -> String) -> String {
+    println!("cargo:rerun-if-env-changed={key}");
+    std::env::var(key)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(default)
+}
+
+fn git_commit_hash() -> String {
+    Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    // The buf module reference (a commit, tag or branch) of the game data protocol to generate from.
+    let game_data_proto_ref = env_or("SEICHI_GAME_DATA_PROTO_REF", || "main".to_string());
+
     process_status(
         Command::new("buf")
             .arg("generate")
-            .arg("buf.build/gigantic-minecraft/seichi-game-data")
+            .arg(format!(
+                "buf.build/gigantic-minecraft/seichi-game-data:{game_data_proto_ref}"
+            ))
             .current_dir(env!("CARGO_MANIFEST_DIR"))
             .status()
             .unwrap(),
     );
 
+    // Docker builds do not have access to the git repository, so the commit can be passed in
+    let git_commit = env_or("GIT_COMMIT_SHA", git_commit_hash);
+
+    println!("cargo:rustc-env=BUILD_GIT_COMMIT={git_commit}");
+    println!("cargo:rustc-env=BUILD_SEICHI_GAME_DATA_PROTO_REF={game_data_proto_ref}");
+
     Ok(())
 }
@@ -0,0 +1,9495 @@
+#![deny(clippy::all, clippy::cargo)]
+#![warn(clippy::nursery, clippy::pedantic)]
+#![allow(clippy::cargo_common_metadata)]
+
+pub mod domain {
+    use anyhow::anyhow;
+    use futures::stream::{self, BoxStream};
+    use futures::{StreamExt, TryStreamExt};
+    use indexmap::IndexMap;
+    use std::cmp::Reverse;
+    use std::collections::{HashMap, HashSet};
+    use std::fmt::{Debug, Display};
+    use std::num::NonZeroU32;
+    use std::sync::{LazyLock, Mutex, PoisonError};
+    use std::time::{Duration, SystemTime};
+
+    /// Player UUID, held in its 16-byte binary form and formatted only when presented.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct PlayerUuid(uuid::Uuid);
+
+    impl PlayerUuid {
+        /// Parses either the hyphenated form or the 32 hex digits without hyphens, in any case.
+        /// Other forms the `uuid` crate understands, such as braced or URN ones, are rejected.
+        #[allow(clippy::missing_errors_doc)]
+        pub fn parse(str: &str) -> anyhow::Result<Self> {
+            if str.len() != uuid::fmt::Simple::LENGTH && str.len() != uuid::fmt::Hyphenated::LENGTH
+            {
+                return Err(anyhow!(
+                    "Expected UUID of 32 hex digits, optionally hyphenated, got {str:?}"
+                ));
+            }
+
+            uuid::Uuid::try_parse(str)
+                .map(Self)
+                .map_err(|e| anyhow!("Expected UUID, got {str:?}: {e}"))
+        }
+
+        #[must_use]
+        pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+            Self(uuid::Uuid::from_bytes(bytes))
+        }
+
+        #[must_use]
+        pub const fn as_bytes(&self) -> &[u8; 16] {
+            self.0.as_bytes()
+        }
+
+        /// Formats the UUID into `buffer` without allocating.
+        pub fn encode_hyphenated<'b>(
+            &self,
+            buffer: &'b mut [u8; uuid::fmt::Hyphenated::LENGTH],
+        ) -> &'b str {
+            self.0.hyphenated().encode_lower(buffer)
+        }
+    }
+
+    impl Display for PlayerUuid {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            Display::fmt(&self.0.hyphenated(), f)
+        }
+    }
+
+    /// Represented by the hyphenated form, as presented everywhere else.
+    impl serde::Serialize for PlayerUuid {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for PlayerUuid {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let str = String::deserialize(deserializer)?;
+            Self::parse(&str).map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// Names of the game servers, interned by [`GameServer::new`].
+    static GAME_SERVER_NAMES: LazyLock<Mutex<HashSet<&'static str>>> =
+        LazyLock::new(Mutex::default);
+
+    /// Game server a record comes from, when statistics are fetched from several of them.
+    ///
+    /// Servers are few and known for the lifetime of the process, so their names are interned
+    /// to keep [`Player`] `Copy`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct GameServer(&'static str);
+
+    impl GameServer {
+        /// Names are restricted to ASCII letters, digits, `-` and `_`, so that they can be
+        /// presented as label values as they are.
+        #[allow(clippy::missing_errors_doc)]
+        pub fn new(name: &str) -> anyhow::Result<Self> {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(anyhow!("Invalid game server name {name:?}"));
+            }
+
+            let mut names = GAME_SERVER_NAMES
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(interned) = names.get(name) {
+                return Ok(Self(interned));
+            }
+            let interned: &'static str = Box::leak(name.into());
+            names.insert(interned);
+            drop(names);
+
+            Ok(Self(interned))
+        }
+
+        /// Every server named so far.
+        pub fn known() -> Vec<Self> {
+            GAME_SERVER_NAMES
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .map(|name| Self(name))
+                .collect()
+        }
+
+        #[must_use]
+        pub const fn as_str(self) -> &'static str {
+            self.0
+        }
+    }
+
+    impl serde::Serialize for GameServer {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(self.0)
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for GameServer {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let name = String::deserialize(deserializer)?;
+            Self::new(&name).map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// Identity of a player. Being a small value, it is copied into the aggregation structures
+    /// rather than shared behind a pointer, which would cost more than the copy itself.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    pub struct Player {
+        pub uuid: PlayerUuid,
+        /// Server the record of the player comes from. `None` when there is a single upstream,
+        /// or once the records of every server have been summed.
+        pub server: Option<GameServer>,
+    }
+
+    impl Player {
+        #[must_use]
+        pub const fn new(uuid: PlayerUuid) -> Self {
+            Self { uuid, server: None }
+        }
+
+        /// The player as recorded on `server`.
+        #[must_use]
+        pub const fn on(self, server: GameServer) -> Self {
+            Self {
+                server: Some(server),
+                ..self
+            }
+        }
+    }
+
+    /// Kinds of statistics that are fetched for each player.
+    ///
+    /// Every kind listed in [`Self::ALL`] is fetched, aggregated, persisted and presented under
+    /// its [`Self::as_str`] name, so adding a statistic only takes a variant here and the RPC
+    /// fetching it in the repositories.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum PlayerDataKind {
+        BreakCount,
+        BuildCount,
+        PlayTicks,
+        VoteCount,
+        LoginCount,
+        AfkTicks,
+    }
+
+    impl PlayerDataKind {
+        pub const ALL: [Self; 6] = [
+            Self::BreakCount,
+            Self::BuildCount,
+            Self::PlayTicks,
+            Self::VoteCount,
+            Self::LoginCount,
+            Self::AfkTicks,
+        ];
+
+        #[must_use]
+        pub const fn as_str(self) -> &'static str {
+            match self {
+                Self::BreakCount => "break_count",
+                Self::BuildCount => "build_count",
+                Self::PlayTicks => "play_ticks",
+                Self::VoteCount => "vote_count",
+                Self::LoginCount => "login_count",
+                Self::AfkTicks => "afk_ticks",
+            }
+        }
+
+        /// Whether the kind is only fetched when opted into, as not every upstream serves it yet.
+        #[must_use]
+        pub const fn is_opt_in(self) -> bool {
+            matches!(self, Self::LoginCount | Self::AfkTicks)
+        }
+
+        /// Position of the kind in [`Self::ALL`], by which per-kind values are indexed.
+        #[must_use]
+        pub const fn index(self) -> usize {
+            self as usize
+        }
+
+        #[must_use]
+        pub fn from_name(name: &str) -> Option<Self> {
+            Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+        }
+    }
+
+    /// Number of game ticks in an hour of play time (20 ticks per second).
+    pub const TICKS_PER_HOUR: u64 = 20 * 60 * 60;
+
+    /// Serializes values indexed by [`PlayerDataKind::index`] as a map keyed by the names of the
+    /// kinds, so that they stay readable when kinds are added. Kinds unknown to the reader are
+    /// dropped and kinds absent from the map are left at their defaults.
+    mod per_kind {
+        use super::PlayerDataKind;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+        use std::collections::HashMap;
+
+        pub fn serialize<S, T>(
+            values: &[T; PlayerDataKind::ALL.len()],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+            T: Serialize,
+        {
+            serializer.collect_map(
+                PlayerDataKind::ALL
+                    .into_iter()
+                    .map(|kind| (kind.as_str(), &values[kind.index()])),
+            )
+        }
+
+        pub fn deserialize<'de, D, T>(
+            deserializer: D,
+        ) -> Result<[T; PlayerDataKind::ALL.len()], D::Error>
+        where
+            D: Deserializer<'de>,
+            T: Deserialize<'de> + Default + Copy,
+        {
+            let mut values = [T::default(); PlayerDataKind::ALL.len()];
+            for (name, value) in HashMap::<String, T>::deserialize(deserializer)? {
+                if let Some(kind) = PlayerDataKind::from_name(&name) {
+                    values[kind.index()] = value;
+                }
+            }
+
+            Ok(values)
+        }
+    }
+
+    #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+    #[serde(default)]
+    pub struct AggregatedPlayerData {
+        /// Value of each kind, indexed by [`PlayerDataKind::index`].
+        #[serde(with = "per_kind")]
+        pub values: [u64; PlayerDataKind::ALL.len()],
+        /// Play time in hours derived from `play_ticks`. `None` unless derivation is enabled.
+        pub play_hours: Option<f64>,
+        pub efficiency: EfficiencyStats,
+        /// Rank of the player by each kind, indexed by [`PlayerDataKind::index`]. `None` unless
+        /// ranks are computed.
+        #[serde(with = "per_kind")]
+        pub ranks: [Option<NonZeroU32>; PlayerDataKind::ALL.len()],
+        /// Whether the value of each kind exceeds its plausibility bound, indexed by
+        /// [`PlayerDataKind::index`]. Implausible values are kept out of the rankings.
+        #[serde(with = "per_kind")]
+        pub implausible: [bool; PlayerDataKind::ALL.len()],
+        /// When the player was last seen on the server. `None` unless last seen times are
+        /// fetched and the upstream knows of the player.
+        pub last_seen: Option<SystemTime>,
+        /// Name the player was last known by. `None` unless names are fetched and the upstream
+        /// knows the name of the player.
+        pub name: Option<String>,
+    }
+
+    impl AggregatedPlayerData {
+        #[must_use]
+        pub const fn get(&self, kind: PlayerDataKind) -> u64 {
+            self.values[kind.index()]
+        }
+
+        pub const fn set(&mut self, kind: PlayerDataKind, value: u64) {
+            self.values[kind.index()] = value;
+        }
+
+        pub fn rank(&self, kind: PlayerDataKind) -> Option<u32> {
+            self.ranks[kind.index()].map(NonZeroU32::get)
+        }
+
+        #[must_use]
+        pub const fn is_implausible(&self, kind: PlayerDataKind) -> bool {
+            self.implausible[kind.index()]
+        }
+
+        #[must_use]
+        pub fn compute_play_hours(&self) -> f64 {
+            play_hours_of(self.get(PlayerDataKind::PlayTicks))
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn play_hours_of(play_ticks: u64) -> f64 {
+        play_ticks as f64 / TICKS_PER_HOUR as f64
+    }
+
+    /// Length of the month by which votes are rated, taken as 30 days.
+    pub const VOTING_MONTH: Duration = Duration::from_hours(30 * 24);
+
+    /// Ratios derived from the statistics of a player. A ratio is `None` unless derivation is
+    /// enabled, when a kind it is derived from is not fetched, or when it would divide by zero.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+    #[serde(default)]
+    pub struct EfficiencyStats {
+        pub breaks_per_play_hour: Option<f64>,
+        pub builds_per_play_hour: Option<f64>,
+        /// Votes per [`VOTING_MONTH`] since votes started being counted.
+        pub votes_per_month: Option<f64>,
+    }
+
+    impl EfficiencyStats {
+        /// Names of the ratios, in the order of [`Self::ratios`].
+        pub const NAMES: [&'static str; 3] = [
+            "breaks_per_play_hour",
+            "builds_per_play_hour",
+            "votes_per_month",
+        ];
+
+        #[must_use]
+        pub const fn ratios(&self) -> [Option<f64>; 3] {
+            [
+                self.breaks_per_play_hour,
+                self.builds_per_play_hour,
+                self.votes_per_month,
+            ]
+        }
+
+        /// Derives the ratios from the value of each kind, which `value_of` yields `None` for if
+        /// it is not fetched. Votes are rated over `voting_period` if it is known.
+        #[allow(clippy::cast_precision_loss)]
+        pub fn derive(
+            value_of: impl Fn(PlayerDataKind) -> Option<u64>,
+            voting_period: Option<Duration>,
+        ) -> Self {
+            let ratio = |kind, denominator: Option<f64>| {
+                let denominator = denominator.filter(|denominator| *denominator > 0.0)?;
+                Some(value_of(kind)? as f64 / denominator)
+            };
+            let play_hours = value_of(PlayerDataKind::PlayTicks).map(play_hours_of);
+            let voting_months =
+                voting_period.map(|period| period.as_secs_f64() / VOTING_MONTH.as_secs_f64());
+
+            Self {
+                breaks_per_play_hour: ratio(PlayerDataKind::BreakCount, play_hours),
+                builds_per_play_hour: ratio(PlayerDataKind::BuildCount, play_hours),
+                votes_per_month: ratio(PlayerDataKind::VoteCount, voting_months),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct KnownAggregatedPlayerData(pub IndexMap<Player, AggregatedPlayerData>);
+
+    /// A player together with their data, as which each entry of [`KnownAggregatedPlayerData`]
+    /// is represented, since players cannot be keys of every format.
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct PlayerEntry<P, D> {
+        player: P,
+        data: D,
+    }
+
+    /// Represented as a list of entries, in the order of the players.
+    impl serde::Serialize for KnownAggregatedPlayerData {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(
+                self.0
+                    .iter()
+                    .map(|(player, data)| PlayerEntry { player, data }),
+            )
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for KnownAggregatedPlayerData {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct EntriesVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for EntriesVisitor {
+                type Value = KnownAggregatedPlayerData;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("a list of players with their data")
+                }
+
+                fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                    self,
+                    mut entries: A,
+                ) -> Result<Self::Value, A::Error> {
+                    let mut map = IndexMap::new();
+                    while let Some(PlayerEntry { player, data }) = entries.next_element()? {
+                        map.insert(player, data);
+                    }
+
+                    Ok(KnownAggregatedPlayerData(map))
+                }
+            }
+
+            deserializer.deserialize_seq(EntriesVisitor)
+        }
+    }
+
+    impl KnownAggregatedPlayerData {
+        /// Whether the data holds records of distinct game servers.
+        #[must_use]
+        pub fn is_per_server(&self) -> bool {
+            self.0.keys().any(|player| player.server.is_some())
+        }
+
+        /// Sums the values of each player over the game servers they were recorded on, keeping
+        /// the latest of their last seen times and the first name they are known by.
+        #[must_use]
+        pub fn merge_servers(self) -> Self {
+            let mut merged = IndexMap::<Player, AggregatedPlayerData>::with_capacity(self.0.len());
+
+            for (player, data) in self.0 {
+                let total = merged
+                    .entry(Player {
+                        server: None,
+                        ..player
+                    })
+                    .or_default();
+                for kind in PlayerDataKind::ALL {
+                    total.set(kind, total.get(kind).saturating_add(data.get(kind)));
+                }
+                total.last_seen = total.last_seen.max(data.last_seen);
+                if total.name.is_none() {
+                    total.name = data.name;
+                }
+            }
+
+            Self(merged)
+        }
+
+        /// Records of the player with `uuid`, one for each game server they were recorded on, or a
+        /// single one if the servers are not kept apart.
+        #[must_use]
+        pub fn records_of(&self, uuid: PlayerUuid) -> Vec<&AggregatedPlayerData> {
+            let player = Player::new(uuid);
+
+            std::iter::once(player)
+                .chain(
+                    GameServer::known()
+                        .into_iter()
+                        .map(|server| player.on(server)),
+                )
+                .filter_map(|player| self.0.get(&player))
+                .collect()
+        }
+
+        /// Ranks the players by each of `kinds`, the largest value first. Players with equal values
+        /// share a rank, and the ranks after them skip the places they share. Players whose value
+        /// is implausible are left unranked.
+        pub fn assign_ranks(&mut self, kinds: &[PlayerDataKind]) {
+            for kind in kinds {
+                let mut order = (0..self.0.len())
+                    .filter(|index| !self.0[*index].is_implausible(*kind))
+                    .collect::<Vec<_>>();
+                order.sort_unstable_by_key(|index| Reverse(self.0[*index].get(*kind)));
+
+                let mut rank = NonZeroU32::MIN;
+                let mut previous_value = None;
+                for (place, index) in (1..).zip(&order) {
+                    let data = &mut self.0[*index];
+                    let value = data.get(*kind);
+                    if previous_value != Some(value) {
+                        rank = NonZeroU32::new(place).unwrap_or(NonZeroU32::MAX);
+                        previous_value = Some(value);
+                    }
+                    data.ranks[kind.index()] = Some(rank);
+                }
+            }
+        }
+
+        /// Number of players whose value of `kind` is implausible.
+        #[must_use]
+        pub fn count_implausible(&self, kind: PlayerDataKind) -> usize {
+            self.0
+                .values()
+                .filter(|data| data.is_implausible(kind))
+                .count()
+        }
+
+        /// Number of players last seen at or after `since`.
+        #[must_use]
+        pub fn count_seen_since(&self, since: SystemTime) -> usize {
+            self.0
+                .values()
+                .filter(|data| data.last_seen.is_some_and(|last_seen| last_seen >= since))
+                .count()
+        }
+    }
+
+    /// Totals of the statistics of the members of a group of players, such as a guild.
+    #[derive(Debug, Clone, Default)]
+    pub struct AggregatedGroupData {
+        /// Number of members the fetched data holds statistics of.
+        pub member_count: u64,
+        /// Sum of each kind over the members, indexed by [`PlayerDataKind::index`].
+        pub values: [u64; PlayerDataKind::ALL.len()],
+    }
+
+    impl AggregatedGroupData {
+        #[must_use]
+        pub const fn get(&self, kind: PlayerDataKind) -> u64 {
+            self.values[kind.index()]
+        }
+    }
+
+    /// Totals of each group, by the name of the group.
+    #[derive(Debug, Clone, Default)]
+    pub struct GroupTotals(pub IndexMap<String, AggregatedGroupData>);
+
+    /// Distribution of the values of a kind over the players. `mean` and `median` are zero when
+    /// there are no values.
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub struct KindSummary {
+        pub count: u64,
+        pub sum: u64,
+        pub mean: f64,
+        pub median: f64,
+        pub max: u64,
+    }
+
+    impl KindSummary {
+        /// Summarizes `values`, reordering them in the process.
+        #[allow(clippy::cast_precision_loss)]
+        pub fn of(values: &mut [u64]) -> Self {
+            let count = values.len();
+            if count == 0 {
+                return Self::default();
+            }
+
+            let sum = values
+                .iter()
+                .fold(0_u64, |sum, value| sum.saturating_add(*value));
+            let (lower, upper_middle, _) = values.select_nth_unstable(count / 2);
+            let upper_middle = *upper_middle;
+            let median = if count.is_multiple_of(2) {
+                let lower_middle = lower.iter().max().copied().unwrap_or(upper_middle);
+                f64::midpoint(lower_middle as f64, upper_middle as f64)
+            } else {
+                upper_middle as f64
+            };
+
+            Self {
+                count: count as u64,
+                sum,
+                mean: sum as f64 / count as f64,
+                median,
+                max: values.iter().max().copied().unwrap_or_default(),
+            }
+        }
+    }
+
+    /// Summary of each fetched kind over the players. Like the ranks, implausible values are left
+    /// out.
+    #[derive(Debug, Clone, Default)]
+    pub struct PopulationSummary {
+        /// Number of players the fetched data holds statistics of.
+        pub player_count: u64,
+        pub kinds: Vec<(PlayerDataKind, KindSummary)>,
+    }
+
+    impl PopulationSummary {
+        #[must_use]
+        pub fn get(&self, kind: PlayerDataKind) -> Option<&KindSummary> {
+            self.kinds
+                .iter()
+                .find_map(|(summarized, summary)| (*summarized == kind).then_some(summary))
+        }
+    }
+
+    /// Value of each fetched kind of every player, summed over the records of the player.
+    ///
+    /// The values are sorted, so that a single player is ranked and placed among them in
+    /// logarithmic time. Like the ranks, implausible values are left out.
+    #[derive(Debug, Clone, Default)]
+    pub struct PopulationStandings {
+        /// Sorted values of each kind, indexed by [`PlayerDataKind::index`].
+        sorted: [Vec<u64>; PlayerDataKind::ALL.len()],
+    }
+
+    impl PopulationStandings {
+        #[must_use]
+        pub fn of(fetched: &FetchedPlayerData) -> Self {
+            let mut standings = Self::default();
+            for kind in PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| fetched.has_kind(*kind))
+            {
+                let mut totals = HashMap::<PlayerUuid, u64>::with_capacity(fetched.data.0.len());
+                for (player, data) in fetched
+                    .data
+                    .0
+                    .iter()
+                    .filter(|(_, data)| !data.is_implausible(kind))
+                {
+                    let total = totals.entry(player.uuid).or_default();
+                    *total = total.saturating_add(data.get(kind));
+                }
+
+                let sorted = &mut standings.sorted[kind.index()];
+                *sorted = totals.into_values().collect();
+                sorted.sort_unstable();
+            }
+
+            standings
+        }
+
+        /// Rank of a player whose value of `kind` is `value`, or `None` if there are no players.
+        /// Ranks are shared the same way as [`KnownAggregatedPlayerData::assign_ranks`] does.
+        #[must_use]
+        pub fn rank_of(&self, kind: PlayerDataKind, value: u64) -> Option<u32> {
+            let sorted = &self.sorted[kind.index()];
+            let larger = sorted.len() - sorted.partition_point(|other| *other <= value);
+
+            (!sorted.is_empty()).then(|| u32::try_from(larger + 1).unwrap_or(u32::MAX))
+        }
+
+        /// Percentage of the players whose value of `kind` is at most `value`, or `None` if there
+        /// are no players.
+        #[allow(clippy::cast_precision_loss)]
+        #[must_use]
+        pub fn percentile_of(&self, kind: PlayerDataKind, value: u64) -> Option<f64> {
+            let sorted = &self.sorted[kind.index()];
+            let at_most = sorted.partition_point(|other| *other <= value);
+
+            (!sorted.is_empty()).then(|| at_most as f64 * 100.0 / sorted.len() as f64)
+        }
+
+        #[must_use]
+        pub fn approximate_size_bytes(&self) -> usize {
+            self.sorted
+                .iter()
+                .map(|sorted| sorted.capacity() * std::mem::size_of::<u64>())
+                .sum()
+        }
+    }
+
+    /// Statistics of a single player. Kinds the player has no record of, or that are not
+    /// fetched, are absent from `counts`.
+    #[derive(Debug, Clone, Default)]
+    pub struct PlayerStats {
+        pub counts: Vec<(PlayerDataKind, u64)>,
+        pub play_hours: Option<f64>,
+        pub efficiency: EfficiencyStats,
+        /// Rank of the player by each kind in the latest snapshot, if ranks are computed.
+        pub ranks: Vec<(PlayerDataKind, u32)>,
+        /// Percentile of the player by each kind within the latest snapshot, if percentiles are
+        /// computed.
+        pub percentiles: Vec<(PlayerDataKind, f64)>,
+        pub last_seen: Option<SystemTime>,
+        pub name: Option<String>,
+        pub season: Option<String>,
+    }
+
+    /// Change of each statistic of a player between two snapshots.
+    #[derive(Debug, Clone, Default)]
+    pub struct AggregatedPlayerDataDelta {
+        /// Change of each kind, indexed by [`PlayerDataKind::index`].
+        pub values: [i64; PlayerDataKind::ALL.len()],
+    }
+
+    impl AggregatedPlayerDataDelta {
+        #[must_use]
+        pub const fn get(&self, kind: PlayerDataKind) -> i64 {
+            self.values[kind.index()]
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct KnownAggregatedPlayerDataDelta(pub IndexMap<Player, AggregatedPlayerDataDelta>);
+
+    /// Approximate memory allocated by `map`: each entry holds its hash next to the key and the
+    /// value, and the hash table holds an index and a control byte per entry.
+    fn approximate_index_map_size<K, V>(map: &IndexMap<K, V>) -> usize {
+        let entry_size = std::mem::size_of::<(usize, K, V)>() + std::mem::size_of::<usize>() + 1;
+
+        map.capacity() * entry_size
+    }
+
+    impl KnownAggregatedPlayerDataDelta {
+        #[must_use]
+        pub fn approximate_size_bytes(&self) -> usize {
+            approximate_index_map_size(&self.0)
+        }
+    }
+
+    /// Aggregated player data together with the kinds that could not be fetched.
+    /// Values of missing or disabled kinds in `data` are left at their defaults and must not be
+    /// presented.
+    #[derive(Debug, Clone, Default)]
+    pub struct FetchedPlayerData {
+        pub data: KnownAggregatedPlayerData,
+        pub missing_kinds: Vec<PlayerDataKind>,
+        /// Kinds that were not fetched because they are disabled by configuration.
+        pub disabled_kinds: Vec<PlayerDataKind>,
+        /// Number of records of each kind that were received for a player already seen.
+        pub duplicate_records: HashMap<PlayerDataKind, u64>,
+        /// Time from the start of the fetch until the last record of each kind was received.
+        pub fetch_durations: HashMap<PlayerDataKind, Duration>,
+        /// Season the statistics are counted in, if known, as some of them are reset every season.
+        pub season: Option<String>,
+    }
+
+    impl FetchedPlayerData {
+        #[must_use]
+        pub const fn is_complete(&self) -> bool {
+            self.missing_kinds.is_empty()
+        }
+
+        #[must_use]
+        pub fn is_enabled(&self, kind: PlayerDataKind) -> bool {
+            !self.disabled_kinds.contains(&kind)
+        }
+
+        #[must_use]
+        pub fn has_kind(&self, kind: PlayerDataKind) -> bool {
+            self.is_enabled(kind) && !self.missing_kinds.contains(&kind)
+        }
+
+        /// The kind that took the longest to fetch, which the whole fetch had to wait for.
+        #[must_use]
+        pub fn slowest_fetch(&self) -> Option<(PlayerDataKind, Duration)> {
+            self.fetch_durations
+                .iter()
+                .max_by_key(|(_, duration)| **duration)
+                .map(|(kind, duration)| (*kind, *duration))
+        }
+    }
+
+    fn signed_difference(current: u64, previous: u64) -> i64 {
+        let difference = i128::from(current) - i128::from(previous);
+        i64::try_from(difference).unwrap_or(if difference < 0 { i64::MIN } else { i64::MAX })
+    }
+
+    impl KnownAggregatedPlayerData {
+        #[must_use]
+        pub fn approximate_size_bytes(&self) -> usize {
+            approximate_index_map_size(&self.0)
+        }
+
+        /// Computes deltas of players known in both `self` and `previous`.
+        /// Players that are absent from `previous` have no baseline and are therefore omitted.
+        #[must_use]
+        pub fn delta_since(&self, previous: &Self) -> KnownAggregatedPlayerDataDelta {
+            let deltas = self
+                .0
+                .iter()
+                .filter_map(|(player, current)| {
+                    let previous = previous.0.get(player)?;
+                    let delta = AggregatedPlayerDataDelta {
+                        values: PlayerDataKind::ALL
+                            .map(|kind| signed_difference(current.get(kind), previous.get(kind))),
+                    };
+
+                    Some((*player, delta))
+                })
+                .collect();
+
+            KnownAggregatedPlayerDataDelta(deltas)
+        }
+    }
+
+    /// State of the connection between a repository and its upstream data source.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum UpstreamConnectionState {
+        Connecting,
+        Ready,
+        Disconnected,
+    }
+
+    impl UpstreamConnectionState {
+        pub const ALL: [Self; 3] = [Self::Connecting, Self::Ready, Self::Disconnected];
+
+        #[must_use]
+        pub const fn as_str(self) -> &'static str {
+            match self {
+                Self::Connecting => "connecting",
+                Self::Ready => "ready",
+                Self::Disconnected => "disconnected",
+            }
+        }
+    }
+
+    /// Health of the translator as a whole, as seen by the clients it serves.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum HealthState {
+        /// Nothing has been fetched from the upstream yet.
+        Startup,
+        /// The latest refresh fetched fresh data from the upstream.
+        Healthy,
+        /// The upstream is failing, but data fetched from it earlier is served in its place.
+        DegradedServingStale,
+        /// The upstream is failing and there is no earlier data to serve in its place.
+        UpstreamDown,
+    }
+
+    impl HealthState {
+        pub const ALL: [Self; 4] = [
+            Self::Startup,
+            Self::Healthy,
+            Self::DegradedServingStale,
+            Self::UpstreamDown,
+        ];
+
+        #[must_use]
+        pub const fn as_str(self) -> &'static str {
+            match self {
+                Self::Startup => "startup",
+                Self::Healthy => "healthy",
+                Self::DegradedServingStale => "degraded_serving_stale",
+                Self::UpstreamDown => "upstream_down",
+            }
+        }
+    }
+
+    /// Error reported by a [`PlayerDataRepository`] when the upstream did not respond in time.
+    #[derive(Debug, Clone)]
+    pub struct UpstreamTimeoutError {
+        pub operation: &'static str,
+    }
+
+    impl std::fmt::Display for UpstreamTimeoutError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "upstream operation {} timed out", self.operation)
+        }
+    }
+
+    impl std::error::Error for UpstreamTimeoutError {}
+
+    /// Error reported by a [`PlayerDataRepository`] that refused to query the upstream,
+    /// for example because the upstream is known to be failing.
+    #[derive(Debug, Clone)]
+    pub struct UpstreamUnavailableError {
+        pub reason: String,
+    }
+
+    impl std::fmt::Display for UpstreamUnavailableError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "upstream is unavailable: {}", self.reason)
+        }
+    }
+
+    impl std::error::Error for UpstreamUnavailableError {}
+
+    /// Records of a kind of player data, yielded as they are received. A failure ends the stream.
+    pub type PlayerDataStream<'a, T> = BoxStream<'a, anyhow::Result<T>>;
+
+    /// Storage keeping the latest player data across restarts.
+    pub trait SnapshotPersistence: Debug + Sync + Send + 'static {
+        #[allow(clippy::missing_errors_doc)]
+        fn save(&self, fetched: &FetchedPlayerData) -> anyhow::Result<()>;
+
+        /// Returns the saved data, or `None` if nothing has been saved yet.
+        #[allow(clippy::missing_errors_doc)]
+        fn load(&self) -> anyhow::Result<Option<FetchedPlayerData>>;
+    }
+
+    #[async_trait::async_trait]
+    pub trait PlayerDataRepository: Debug + Sync + Send + 'static {
+        /// Records of `kind` as pairs of the player and the value of the kind.
+        fn get_all_of(&self, kind: PlayerDataKind) -> PlayerDataStream<'_, (Player, u64)>;
+
+        /// When each player was last seen on the server.
+        fn get_all_last_seen(&self) -> PlayerDataStream<'_, (Player, SystemTime)>;
+
+        /// Name each player was last known by. Repositories whose source does not know the names
+        /// of the players, or sends them along with the play ticks, yield none.
+        fn get_all_names(&self) -> PlayerDataStream<'_, (Player, String)> {
+            stream::empty().boxed()
+        }
+
+        /// Play ticks together with the name each player was last known by, for repositories
+        /// whose source sends the names along with the play ticks. The others yield no names
+        /// here, but from [`Self::get_all_names`].
+        fn get_all_play_ticks_with_names(
+            &self,
+        ) -> PlayerDataStream<'_, (Player, (u64, Option<String>))> {
+            self.get_all_of(PlayerDataKind::PlayTicks)
+                .map_ok(|(player, play_ticks)| (player, (play_ticks, None)))
+                .boxed()
+        }
+
+        /// Value of `kind` for a single player, or `None` if the player has no record of it.
+        /// Repositories whose source can look up a single player should override this; by
+        /// default, records are scanned until the player is found.
+        async fn get_count_for(
+            &self,
+            uuid: &PlayerUuid,
+            kind: PlayerDataKind,
+        ) -> anyhow::Result<Option<u64>> {
+            let mut records = self.get_all_of(kind);
+
+            while let Some((player, value)) = records.try_next().await? {
+                if player.uuid == *uuid {
+                    return Ok(Some(value));
+                }
+            }
+
+            Ok(None)
+        }
+
+        /// When a single player was last seen, or `None` if the player has not been seen. Like
+        /// [`Self::get_count_for`], records are scanned until the player is found by default.
+        async fn get_last_seen_for(&self, uuid: &PlayerUuid) -> anyhow::Result<Option<SystemTime>> {
+            let mut records = self.get_all_last_seen();
+
+            while let Some((player, last_seen)) = records.try_next().await? {
+                if player.uuid == *uuid {
+                    return Ok(Some(last_seen));
+                }
+            }
+
+            Ok(None)
+        }
+
+        /// Name a single player was last known by, or `None` if it is unknown. Like
+        /// [`Self::get_count_for`], records are scanned until the player is found by default.
+        async fn get_name_for(&self, uuid: &PlayerUuid) -> anyhow::Result<Option<String>> {
+            let mut records = self.get_all_names();
+
+            while let Some((player, name)) = records.try_next().await? {
+                if player.uuid == *uuid {
+                    return Ok(Some(name));
+                }
+            }
+
+            Ok(None)
+        }
+
+        /// Season the source is currently counting the statistics in, or `None` if it does not
+        /// divide them into seasons.
+        async fn get_current_season(&self) -> anyhow::Result<Option<String>> {
+            Ok(None)
+        }
+
+        /// Checks whether the repository is currently able to reach its data source.
+        async fn check_readiness(&self) -> anyhow::Result<()>;
+
+        fn connection_state(&self) -> UpstreamConnectionState;
+    }
+
+    /// Source of the groups, such as guilds, that players are members of.
+    #[async_trait::async_trait]
+    pub trait GroupMembershipRepository: Debug + Sync + Send + 'static {
+        /// Pairs of a player and the name of a group they are a member of. A player may be a
+        /// member of several groups.
+        async fn get_all_memberships(&self) -> anyhow::Result<Vec<(PlayerUuid, String)>>;
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn uuid(index: u8) -> PlayerUuid {
+            let mut bytes = [0; 16];
+            bytes[15] = index;
+            PlayerUuid::from_bytes(bytes)
+        }
+
+        #[test]
+        fn parse_hyphenated_and_hyphenless_uuids_in_any_case() {
+            let hyphenated = PlayerUuid::parse("0f8c4a1e-2b3d-4e5f-8a9b-0c1d2e3f4a5b").unwrap();
+
+            assert_eq!(
+                PlayerUuid::parse("0F8C4A1E-2B3D-4E5F-8A9B-0C1D2E3F4A5B").unwrap(),
+                hyphenated
+            );
+            assert_eq!(
+                PlayerUuid::parse("0f8c4a1e2b3d4e5f8a9b0c1d2e3f4a5b").unwrap(),
+                hyphenated
+            );
+            assert_eq!(
+                hyphenated.to_string(),
+                "0f8c4a1e-2b3d-4e5f-8a9b-0c1d2e3f4a5b"
+            );
+        }
+
+        #[test]
+        fn reject_uuids_of_other_forms() {
+            for invalid in [
+                "",
+                "0f8c4a1e-2b3d-4e5f-8a9b-0c1d2e3f4a5",
+                "0f8c4a1e-2b3d-4e5f-8a9b-0c1d2e3f4a5g",
+                "{0f8c4a1e-2b3d-4e5f-8a9b-0c1d2e3f4a5b}",
+                "urn:uuid:0f8c4a1e-2b3d-4e5f-8a9b-0c1d2e3f4a5b",
+            ] {
+                let error = PlayerUuid::parse(invalid).unwrap_err();
+
+                assert!(
+                    error.to_string().contains(&format!("{invalid:?}")),
+                    "{error}"
+                );
+            }
+        }
+
+        #[test]
+        fn summarize_an_odd_number_of_values() {
+            let summary = KindSummary::of(&mut [3, 1, 2]);
+
+            assert_eq!(
+                summary,
+                KindSummary {
+                    count: 3,
+                    sum: 6,
+                    mean: 2.0,
+                    median: 2.0,
+                    max: 3,
+                }
+            );
+        }
+
+        #[test]
+        fn summarize_an_even_number_of_values_with_the_middle_two() {
+            let summary = KindSummary::of(&mut [4, 1, 3, 2]);
+
+            assert!((summary.median - 2.5).abs() < f64::EPSILON);
+            assert_eq!(summary.max, 4);
+        }
+
+        #[test]
+        fn summarize_no_values() {
+            assert_eq!(KindSummary::of(&mut []), KindSummary::default());
+        }
+
+        #[test]
+        fn rank_and_place_players_by_their_totals_over_servers() {
+            let (first, second) = (
+                GameServer::new("s1").unwrap(),
+                GameServer::new("s2").unwrap(),
+            );
+            let record = |value, implausible| {
+                let mut data = AggregatedPlayerData::default();
+                data.set(PlayerDataKind::BreakCount, value);
+                data.implausible[PlayerDataKind::BreakCount.index()] = implausible;
+                data
+            };
+            let fetched = FetchedPlayerData {
+                data: KnownAggregatedPlayerData(IndexMap::from([
+                    (Player::new(uuid(1)).on(first), record(10, false)),
+                    (Player::new(uuid(1)).on(second), record(10, false)),
+                    (Player::new(uuid(2)).on(first), record(15, false)),
+                    (Player::new(uuid(3)).on(first), record(5, false)),
+                    (Player::new(uuid(4)).on(first), record(1_000, true)),
+                ])),
+                ..FetchedPlayerData::default()
+            };
+
+            let standings = PopulationStandings::of(&fetched);
+
+            let kind = PlayerDataKind::BreakCount;
+            assert_eq!(standings.rank_of(kind, 20), Some(1));
+            assert_eq!(standings.rank_of(kind, 15), Some(2));
+            assert_eq!(standings.rank_of(kind, 5), Some(3));
+            assert_eq!(standings.percentile_of(kind, 15), Some(200.0 / 3.0));
+            assert_eq!(standings.percentile_of(kind, 20), Some(100.0));
+            assert_eq!(standings.rank_of(PlayerDataKind::BuildCount, 0), Some(1));
+        }
+
+        #[test]
+        fn rank_no_one_among_no_players() {
+            let standings = PopulationStandings::default();
+
+            assert_eq!(standings.rank_of(PlayerDataKind::BreakCount, 0), None);
+            assert_eq!(standings.percentile_of(PlayerDataKind::BreakCount, 0), None);
+        }
+    }
+}
+
+pub use seichi_timed_stats_config as config;
+
+pub mod use_cases {
+    use crate::domain::{
+        play_hours_of, AggregatedGroupData, AggregatedPlayerData, EfficiencyStats,
+        FetchedPlayerData, GroupMembershipRepository, GroupTotals, HealthState, KindSummary,
+        KnownAggregatedPlayerData, KnownAggregatedPlayerDataDelta, Player, PlayerDataKind,
+        PlayerDataRepository, PlayerStats, PlayerUuid, PopulationStandings, PopulationSummary,
+        SnapshotPersistence,
+    };
+    use config::DuplicateRecordPolicy;
+    use futures::future::{self, BoxFuture, Shared};
+    use futures::stream::{self, Stream};
+    use futures::{FutureExt, StreamExt, TryStreamExt};
+    use indexmap::IndexMap;
+    use std::collections::hash_map::Entry;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex, PoisonError};
+    use std::time::{Duration, Instant, SystemTime};
+    use tokio::time::MissedTickBehavior;
+
+    pub mod config {
+        use crate::config::{ConfigSources, Settings};
+        use crate::domain::{PlayerDataKind, TICKS_PER_HOUR};
+        use std::time::{Duration, SystemTime};
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        #[allow(clippy::struct_excessive_bools)]
+        pub struct DerivedStatsConfig {
+            #[serde(default)]
+            pub derive_play_hours: bool,
+            /// Derive ratios such as breaks per play hour and votes per month.
+            #[serde(default)]
+            pub derive_efficiency_stats: bool,
+            /// When votes started being counted, in RFC 3339 (e.g. `2022-04-01T00:00:00Z`).
+            /// Votes per month are only derived when it is set.
+            pub votes_counted_since: Option<String>,
+            /// Rank the players by each kind, presented by the `/api` routes.
+            #[serde(default)]
+            pub compute_ranks: bool,
+            /// Present the ranks of the players ranked within this many places on `/metrics` too.
+            pub rank_series_top_n: Option<u32>,
+            /// Place each player within the latest snapshot by the percentage of players whose
+            /// value they reach, presented by the `/api` routes.
+            #[serde(default)]
+            pub compute_percentiles: bool,
+            #[serde(default)]
+            pub expose_deltas: bool,
+            /// Players last seen longer ago than this are left out of the fetched data. Players
+            /// whose last seen time is unknown are kept.
+            pub active_player_window_seconds: Option<u64>,
+        }
+
+        impl Settings for DerivedStatsConfig {}
+
+        impl DerivedStatsConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let config = sources.extract::<Self>()?;
+
+                if let Some(since) = &config.votes_counted_since {
+                    if humantime::parse_rfc3339_weak(since).is_err() {
+                        return Err(sources.invalid(
+                            "VOTES_COUNTED_SINCE",
+                            format!("{since} is not an RFC 3339 timestamp"),
+                        ));
+                    }
+                }
+                if config.rank_series_top_n.is_some() && !config.compute_ranks {
+                    return Err(sources
+                        .invalid("RANK_SERIES_TOP_N", "requires COMPUTE_RANKS to be enabled"));
+                }
+
+                Ok(config)
+            }
+
+            pub fn active_player_window(&self) -> Option<Duration> {
+                self.active_player_window_seconds.map(Duration::from_secs)
+            }
+
+            /// Time elapsed since votes started being counted, if it is known and has passed.
+            #[must_use]
+            pub fn voting_period(&self) -> Option<Duration> {
+                let since =
+                    humantime::parse_rfc3339_weak(self.votes_counted_since.as_ref()?).ok()?;
+                SystemTime::now().duration_since(since).ok()
+            }
+        }
+
+        #[derive(
+            serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq,
+        )]
+        #[serde(rename_all = "snake_case")]
+        pub enum DuplicateRecordPolicy {
+            /// Keep the largest value, as the statistics only ever grow.
+            #[default]
+            Max,
+            /// Keep the value received last.
+            Last,
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        #[allow(clippy::struct_excessive_bools)]
+        pub struct FetchConfig {
+            /// Serve the kinds that could be fetched instead of failing when some of them could not.
+            #[serde(default)]
+            pub tolerate_partial_failures: bool,
+            /// Kinds of player data that are neither fetched nor presented, separated by commas
+            /// (e.g. `vote_count`).
+            #[serde(default)]
+            pub disabled_player_data_kinds: Vec<String>,
+            /// Kinds that not every upstream serves yet, such as `login_count` and `afk_ticks`,
+            /// fetched only when listed here, separated by commas.
+            #[serde(default)]
+            pub opt_in_player_data_kinds: Vec<String>,
+            /// Fetch when each player was last seen, which not every upstream serves yet.
+            #[serde(default)]
+            pub fetch_last_seen: bool,
+            /// Fetch the name each player was last known by. gRPC upstreams only send names along
+            /// with other records, so their names are collected from the play ticks.
+            #[serde(default)]
+            pub fetch_player_names: bool,
+            /// How to resolve several records of the same kind received for a player.
+            #[serde(default)]
+            pub duplicate_record_policy: DuplicateRecordPolicy,
+            /// Season to label the statistics with. When unset, the upstream is asked for it.
+            pub season: Option<String>,
+            /// Keep the statistics of each game server apart, labelled with the server, instead
+            /// of summing them per player. Only matters when several game servers are fetched.
+            #[serde(default)]
+            pub per_game_server_statistics: bool,
+            /// Largest plausible values of kinds, as `kind=value` pairs separated by commas
+            /// (e.g. `break_count=100000000`). Players exceeding them are reported and left out
+            /// of the rankings.
+            #[serde(default)]
+            pub plausibility_bounds: Vec<String>,
+            /// When the statistics started being counted, in RFC 3339. Play and AFK ticks adding
+            /// up to more than the time elapsed since then are implausible.
+            pub statistics_counted_since: Option<String>,
+            /// The two above, parsed once when the configuration is loaded.
+            #[serde(skip)]
+            parsed_plausibility_bounds: PlausibilityBounds,
+        }
+
+        /// Plausibility bounds of a [`FetchConfig`].
+        #[derive(Debug, Clone, Default)]
+        pub struct PlausibilityBounds {
+            /// Bound of each kind, indexed by [`PlayerDataKind::index`].
+            fixed: [Option<u64>; PlayerDataKind::ALL.len()],
+            /// When the statistics started being counted, which bounds the play and AFK ticks.
+            counted_since: Option<SystemTime>,
+        }
+
+        impl PlausibilityBounds {
+            /// Largest plausible value of each kind as of `now`, indexed by
+            /// [`PlayerDataKind::index`].
+            #[must_use]
+            pub fn as_of(&self, now: SystemTime) -> [Option<u64>; PlayerDataKind::ALL.len()] {
+                let mut bounds = self.fixed;
+
+                let elapsed = self
+                    .counted_since
+                    .and_then(|since| now.duration_since(since).ok());
+                if let Some(elapsed) = elapsed {
+                    let elapsed_ticks = elapsed.as_secs().saturating_mul(TICKS_PER_HOUR) / 3600;
+                    for kind in [PlayerDataKind::PlayTicks, PlayerDataKind::AfkTicks] {
+                        let bound = &mut bounds[kind.index()];
+                        *bound =
+                            Some(bound.map_or(elapsed_ticks, |bound| bound.min(elapsed_ticks)));
+                    }
+                }
+
+                bounds
+            }
+        }
+
+        /// Parses a `kind=value` plausibility bound.
+        fn parse_plausibility_bound(entry: &str) -> anyhow::Result<(PlayerDataKind, u64)> {
+            let (name, bound) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("expected kind=value, got {entry}"))?;
+            let kind = PlayerDataKind::from_name(name.trim())
+                .ok_or_else(|| anyhow::anyhow!("unknown player data kind {name}"))?;
+            let bound = bound
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("invalid bound of {name}: {bound}"))?;
+
+            Ok((kind, bound))
+        }
+
+        impl Settings for FetchConfig {}
+
+        impl FetchConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let mut config = sources.extract::<Self>()?;
+
+                for name in config
+                    .disabled_player_data_kinds
+                    .iter()
+                    .filter(|name| !name.is_empty())
+                {
+                    if PlayerDataKind::from_name(name).is_none() {
+                        return Err(sources.invalid(
+                            "DISABLED_PLAYER_DATA_KINDS",
+                            format!("unknown player data kind {name}"),
+                        ));
+                    }
+                }
+                for name in config
+                    .opt_in_player_data_kinds
+                    .iter()
+                    .filter(|name| !name.is_empty())
+                {
+                    if !PlayerDataKind::from_name(name).is_some_and(PlayerDataKind::is_opt_in) {
+                        return Err(sources.invalid(
+                            "OPT_IN_PLAYER_DATA_KINDS",
+                            format!("{name} is not a player data kind to opt into"),
+                        ));
+                    }
+                }
+                if config.season.as_ref().is_some_and(String::is_empty) {
+                    return Err(sources.invalid("SEASON", "must not be empty"));
+                }
+                for entry in config
+                    .plausibility_bounds
+                    .iter()
+                    .filter(|entry| !entry.is_empty())
+                {
+                    match parse_plausibility_bound(entry) {
+                        Ok((kind, bound)) => {
+                            config.parsed_plausibility_bounds.fixed[kind.index()] = Some(bound);
+                        }
+                        Err(e) => return Err(sources.invalid("PLAUSIBILITY_BOUNDS", e.to_string())),
+                    }
+                }
+                if let Some(since) = &config.statistics_counted_since {
+                    let Ok(since) = humantime::parse_rfc3339_weak(since) else {
+                        return Err(sources.invalid(
+                            "STATISTICS_COUNTED_SINCE",
+                            format!("{since} is not an RFC 3339 timestamp"),
+                        ));
+                    };
+                    config.parsed_plausibility_bounds.counted_since = Some(since);
+                }
+                if PlayerDataKind::ALL
+                    .into_iter()
+                    .all(|kind| config.is_disabled(kind))
+                {
+                    return Err(sources.invalid(
+                        "DISABLED_PLAYER_DATA_KINDS",
+                        "every kind of player data is disabled",
+                    ));
+                }
+
+                Ok(config)
+            }
+
+            #[must_use]
+            pub const fn plausibility_bounds(&self) -> &PlausibilityBounds {
+                &self.parsed_plausibility_bounds
+            }
+
+            #[must_use]
+            pub fn is_disabled(&self, kind: PlayerDataKind) -> bool {
+                let listed = |names: &[String]| names.iter().any(|name| name == kind.as_str());
+
+                listed(&self.disabled_player_data_kinds)
+                    || (kind.is_opt_in() && !listed(&self.opt_in_player_data_kinds))
+            }
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        pub struct CacheConfig {
+            /// How long fetched data is served without refreshing it. `0` disables caching.
+            #[serde(default)]
+            pub cache_ttl_millis: u64,
+            /// How long past the TTL a snapshot may still be served while it is refreshed in the
+            /// background. `0` makes every scrape past the TTL wait for the refresh.
+            #[serde(default)]
+            pub cache_stale_while_revalidate_millis: u64,
+            /// Approximate memory the cached snapshots and expositions may take up. Past it,
+            /// cached artifacts are dropped until they fit again, except for the latest snapshot,
+            /// which is kept even if it alone exceeds the budget.
+            pub cache_memory_budget_bytes: Option<usize>,
+        }
+
+        impl Settings for CacheConfig {}
+
+        impl CacheConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                sources.extract()
+            }
+
+            #[must_use]
+            pub const fn cache_ttl(&self) -> Duration {
+                Duration::from_millis(self.cache_ttl_millis)
+            }
+
+            #[must_use]
+            pub const fn stale_while_revalidate(&self) -> Duration {
+                Duration::from_millis(self.cache_stale_while_revalidate_millis)
+            }
+        }
+
+        const fn default_error_log_summary_interval_seconds() -> u64 {
+            300
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+        pub struct ErrorLogConfig {
+            /// How long identical errors are counted rather than logged after the first of them.
+            #[serde(default = "default_error_log_summary_interval_seconds")]
+            pub error_log_summary_interval_seconds: u64,
+        }
+
+        impl Settings for ErrorLogConfig {}
+
+        impl ErrorLogConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                sources.extract()
+            }
+
+            #[must_use]
+            pub const fn summary_interval(&self) -> Duration {
+                Duration::from_secs(self.error_log_summary_interval_seconds)
+            }
+        }
+    }
+
+    /// Number of records folded at once on the blocking pool.
+    const AGGREGATION_BATCH_SIZE: usize = 16_384;
+
+    /// Set of kinds, as bits indexed by [`PlayerDataKind::index`].
+    type KindBits = u64;
+
+    const _: () = assert!(
+        PlayerDataKind::ALL.len() <= KindBits::BITS as usize,
+        "KindBits must have a bit for every kind"
+    );
+
+    /// Aggregated data being built from the records received so far.
+    #[derive(Debug, Default)]
+    struct Aggregation {
+        result_map: IndexMap<Player, AggregatedPlayerData>,
+        // Kinds received for each player, indexed like the entries of `result_map`
+        received_kinds: Vec<KindBits>,
+        duplicate_records: HashMap<PlayerDataKind, u64>,
+    }
+
+    impl Aggregation {
+        fn fold(
+            &mut self,
+            records: Vec<(PlayerDataKind, Player, u64, Option<String>)>,
+            duplicate_record_policy: DuplicateRecordPolicy,
+        ) {
+            for (kind, player, value, name) in records {
+                let entry = self.result_map.entry(player);
+                let index = entry.index();
+                let data = entry.or_default();
+                if index == self.received_kinds.len() {
+                    self.received_kinds.push(0);
+                }
+
+                let kind_bit: KindBits = 1 << kind.index();
+                if self.received_kinds[index] & kind_bit == 0 {
+                    self.received_kinds[index] |= kind_bit;
+                    data.set(kind, value);
+                } else {
+                    *self.duplicate_records.entry(kind).or_insert(0) += 1;
+                    let value = match duplicate_record_policy {
+                        DuplicateRecordPolicy::Max => data.get(kind).max(value),
+                        DuplicateRecordPolicy::Last => value,
+                    };
+                    data.set(kind, value);
+                }
+                if name.is_some() {
+                    data.name = name;
+                }
+            }
+        }
+    }
+
+    /// The configured season, or else the one the repository reports. The season only labels
+    /// the statistics, so failing to fetch it leaves it unknown rather than failing the fetch.
+    async fn current_season(
+        repository: &dyn PlayerDataRepository,
+        fetch_config: &config::FetchConfig,
+    ) -> Option<String> {
+        if let Some(season) = &fetch_config.season {
+            return Some(season.clone());
+        }
+
+        repository.get_current_season().await.unwrap_or_else(|e| {
+            tracing::warn!("failed to fetch the current season: {:?}", e);
+            None
+        })
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct GetAllPlayerDataUseCase {
+        pub repository: Arc<dyn PlayerDataRepository>,
+        pub derived_stats_config: config::DerivedStatsConfig,
+        pub fetch_config: config::FetchConfig,
+    }
+
+    impl GetAllPlayerDataUseCase {
+        /// Streams the records of an enabled kind as `(player, value, name)` triples tagged with
+        /// the kind. Names come along with the play ticks of some repositories, if names are
+        /// fetched.
+        fn records_of(
+            &self,
+            kind: PlayerDataKind,
+        ) -> impl Stream<
+            Item = (
+                PlayerDataKind,
+                anyhow::Result<(Player, u64, Option<String>)>,
+            ),
+        > + Send
+               + '_ {
+            let records =
+                if kind == PlayerDataKind::PlayTicks && self.fetch_config.fetch_player_names {
+                    self.repository
+                        .get_all_play_ticks_with_names()
+                        .map_ok(|(player, (value, name))| (player, value, name))
+                        .boxed()
+                } else {
+                    self.repository
+                        .get_all_of(kind)
+                        .map_ok(|(player, value)| (player, value, None))
+                        .boxed()
+                };
+
+            records.map(move |record| (kind, record))
+        }
+
+        /// Fetches every enabled kind together with the last seen times, the names and the
+        /// season, and derives the statistics that are enabled from them.
+        #[tracing::instrument(skip_all)]
+        pub async fn get_all_known_aggregated_player_data(
+            &self,
+        ) -> anyhow::Result<FetchedPlayerData> {
+            let (fetched, last_seen, names, season) = future::join4(
+                self.aggregate_kinds(),
+                self.fetch_last_seen(),
+                self.fetch_names(),
+                current_season(self.repository.as_ref(), &self.fetch_config),
+            )
+            .await;
+            let mut fetched = fetched?;
+            fetched.season = season;
+
+            if let Some(last_seen) = last_seen? {
+                for (player, last_seen) in last_seen {
+                    if let Some(data) = fetched.data.0.get_mut(&player) {
+                        data.last_seen = Some(last_seen);
+                    }
+                }
+            }
+            if let Some(names) = names? {
+                for (player, name) in names {
+                    if let Some(data) = fetched.data.0.get_mut(&player) {
+                        data.name = Some(name);
+                    }
+                }
+            }
+
+            if !self.fetch_config.per_game_server_statistics && fetched.data.is_per_server() {
+                fetched.data = std::mem::take(&mut fetched.data).merge_servers();
+            }
+
+            if let Some(cutoff) = self
+                .derived_stats_config
+                .active_player_window()
+                .and_then(|window| SystemTime::now().checked_sub(window))
+            {
+                fetched
+                    .data
+                    .0
+                    .retain(|_, data| data.last_seen.is_none_or(|last_seen| last_seen >= cutoff));
+            }
+
+            self.quarantine_implausible_values(&mut fetched);
+
+            if self.derived_stats_config.derive_play_hours
+                && fetched.has_kind(PlayerDataKind::PlayTicks)
+            {
+                for data in fetched.data.0.values_mut() {
+                    data.play_hours = Some(data.compute_play_hours());
+                }
+            }
+
+            if self.derived_stats_config.derive_efficiency_stats {
+                let voting_period = self.derived_stats_config.voting_period();
+                let fetched_kinds = PlayerDataKind::ALL.map(|kind| fetched.has_kind(kind));
+                for data in fetched.data.0.values_mut() {
+                    data.efficiency = EfficiencyStats::derive(
+                        |kind| fetched_kinds[kind.index()].then(|| data.get(kind)),
+                        voting_period,
+                    );
+                }
+            }
+
+            if self.derived_stats_config.compute_ranks {
+                let ranked_kinds = PlayerDataKind::ALL
+                    .into_iter()
+                    .filter(|kind| fetched.has_kind(*kind))
+                    .collect::<Vec<_>>();
+                fetched = tokio::task::spawn_blocking(move || {
+                    fetched.data.assign_ranks(&ranked_kinds);
+                    fetched
+                })
+                .await?;
+            }
+
+            Ok(fetched)
+        }
+
+        /// Flags the values exceeding their plausibility bounds, which
+        /// [`GetPlayerDataSnapshotUseCase`] reports when they are first flagged.
+        fn quarantine_implausible_values(&self, fetched: &mut FetchedPlayerData) {
+            let bounds = self
+                .fetch_config
+                .plausibility_bounds()
+                .as_of(SystemTime::now());
+            let bounded_kinds = PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| bounds[kind.index()].is_some() && fetched.has_kind(*kind))
+                .collect::<Vec<_>>();
+
+            for data in fetched.data.0.values_mut() {
+                for kind in &bounded_kinds {
+                    if bounds[kind.index()].is_some_and(|bound| data.get(*kind) > bound) {
+                        data.implausible[kind.index()] = true;
+                    }
+                }
+            }
+        }
+
+        /// When each player was last seen, if enabled. A failure leaves the times unknown if
+        /// partial failures are tolerated.
+        async fn fetch_last_seen(&self) -> anyhow::Result<Option<Vec<(Player, SystemTime)>>> {
+            if !self.fetch_config.fetch_last_seen {
+                return Ok(None);
+            }
+
+            match self.repository.get_all_last_seen().try_collect().await {
+                Ok(last_seen) => Ok(Some(last_seen)),
+                Err(e) if self.fetch_config.tolerate_partial_failures => {
+                    tracing::warn!("failed to fetch last seen times: {:?}", e);
+                    Ok(None)
+                }
+                Err(e) => Err(e.context("Failed to fetch last seen times")),
+            }
+        }
+
+        /// The name of each player, if enabled. Like the last seen times, a failure leaves the
+        /// names unknown if partial failures are tolerated.
+        async fn fetch_names(&self) -> anyhow::Result<Option<Vec<(Player, String)>>> {
+            if !self.fetch_config.fetch_player_names {
+                return Ok(None);
+            }
+
+            match self.repository.get_all_names().try_collect().await {
+                Ok(names) => Ok(Some(names)),
+                Err(e) if self.fetch_config.tolerate_partial_failures => {
+                    tracing::warn!("failed to fetch player names: {:?}", e);
+                    Ok(None)
+                }
+                Err(e) => Err(e.context("Failed to fetch player names")),
+            }
+        }
+
+        /// Folds the records of every enabled kind into the aggregated data as they arrive, so
+        /// that no kind has to be held in memory as a whole. Records are folded in batches on the
+        /// blocking pool to keep the executor responsive. A failed kind is recorded as missing
+        /// if partial failures are tolerated, in which case the values it yielded before the
+        /// failure are not presented.
+        async fn aggregate_kinds(&self) -> anyhow::Result<FetchedPlayerData> {
+            let disabled_kinds = PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| self.fetch_config.is_disabled(*kind))
+                .collect::<Vec<_>>();
+
+            let mut batches = stream::select_all(
+                PlayerDataKind::ALL
+                    .into_iter()
+                    .filter(|kind| !disabled_kinds.contains(kind))
+                    .map(|kind| self.records_of(kind)),
+            )
+            .ready_chunks(AGGREGATION_BATCH_SIZE);
+
+            let duplicate_record_policy = self.fetch_config.duplicate_record_policy;
+            let mut aggregation = Aggregation::default();
+            let mut missing_kinds = Vec::new();
+            let started_at = Instant::now();
+            let mut fetch_durations = HashMap::new();
+
+            while let Some(batch) = batches.next().await {
+                let received_at = started_at.elapsed();
+                let mut records = Vec::with_capacity(batch.len());
+
+                for (kind, record) in batch {
+                    if missing_kinds.contains(&kind) {
+                        continue;
+                    }
+                    fetch_durations.insert(kind, received_at);
+
+                    match record {
+                        Ok((player, value, name)) => records.push((kind, player, value, name)),
+                        Err(e) if self.fetch_config.tolerate_partial_failures => {
+                            tracing::warn!("failed to fetch {}: {:?}", kind.as_str(), e);
+                            missing_kinds.push(kind);
+                        }
+                        Err(e) => {
+                            return Err(e.context(format!("Failed to fetch {}", kind.as_str())))
+                        }
+                    }
+                }
+
+                aggregation = tokio::task::spawn_blocking(move || {
+                    aggregation.fold(records, duplicate_record_policy);
+                    aggregation
+                })
+                .await?;
+            }
+            let Aggregation {
+                result_map,
+                duplicate_records,
+                ..
+            } = aggregation;
+
+            if missing_kinds.len() + disabled_kinds.len() == PlayerDataKind::ALL.len() {
+                return Err(anyhow::anyhow!("Failed to fetch every kind of player data"));
+            }
+
+            for (kind, count) in &duplicate_records {
+                tracing::warn!(
+                    "received {} duplicate records of {}, resolved with {:?}",
+                    count,
+                    kind.as_str(),
+                    self.fetch_config.duplicate_record_policy
+                );
+            }
+
+            Ok(FetchedPlayerData {
+                data: KnownAggregatedPlayerData(result_map),
+                missing_kinds,
+                disabled_kinds,
+                duplicate_records,
+                fetch_durations,
+                season: None,
+            })
+        }
+    }
+
+    /// Records of a single player, summed over the game servers they were recorded on.
+    #[derive(Debug)]
+    struct PlayerRecords {
+        counts: Vec<(PlayerDataKind, u64)>,
+        last_seen: Option<SystemTime>,
+        name: Option<String>,
+        season: Option<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct GetPlayerDataUseCase {
+        pub repository: Arc<dyn PlayerDataRepository>,
+        pub derived_stats_config: config::DerivedStatsConfig,
+        pub fetch_config: config::FetchConfig,
+        /// Latest snapshot, if a fresh one is cached. The player is looked up in it rather than
+        /// in the repository, and is only ranked and placed when it is there.
+        pub cached_snapshot: Option<Arc<PlayerDataSnapshot>>,
+    }
+
+    impl GetPlayerDataUseCase {
+        /// Statistics of a single player, or `None` if the player is unknown. The records of a
+        /// player on several game servers are summed, and the kinds of which every value of the
+        /// player is implausible are left out.
+        #[tracing::instrument(skip(self))]
+        pub async fn get_player_data(
+            &self,
+            uuid: &PlayerUuid,
+        ) -> anyhow::Result<Option<PlayerStats>> {
+            let records = match &self.cached_snapshot {
+                Some(snapshot) => Self::cached_records_of(&snapshot.fetched, *uuid),
+                None => self.fetch_records_of(uuid).await?,
+            };
+            let Some(PlayerRecords {
+                counts,
+                last_seen,
+                name,
+                season,
+            }) = records
+            else {
+                return Ok(None);
+            };
+            let standings = self
+                .cached_snapshot
+                .as_ref()
+                .map(|snapshot| snapshot.standings.as_ref());
+
+            let play_hours = counts
+                .iter()
+                .find(|(kind, _)| *kind == PlayerDataKind::PlayTicks)
+                .filter(|_| self.derived_stats_config.derive_play_hours)
+                .map(|(_, play_ticks)| play_hours_of(*play_ticks));
+            let efficiency = if self.derived_stats_config.derive_efficiency_stats {
+                EfficiencyStats::derive(
+                    |kind| {
+                        counts
+                            .iter()
+                            .find(|(counted_kind, _)| *counted_kind == kind)
+                            .map(|(_, count)| *count)
+                    },
+                    self.derived_stats_config.voting_period(),
+                )
+            } else {
+                EfficiencyStats::default()
+            };
+            let ranks = standings
+                .filter(|_| self.derived_stats_config.compute_ranks)
+                .map(|standings| {
+                    counts
+                        .iter()
+                        .filter_map(|(kind, count)| {
+                            Some((*kind, standings.rank_of(*kind, *count)?))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let percentiles = standings
+                .filter(|_| self.derived_stats_config.compute_percentiles)
+                .map(|standings| {
+                    counts
+                        .iter()
+                        .filter_map(|(kind, count)| {
+                            Some((*kind, standings.percentile_of(*kind, *count)?))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Ok(Some(PlayerStats {
+                counts,
+                play_hours,
+                efficiency,
+                ranks,
+                percentiles,
+                last_seen,
+                name,
+                season,
+            }))
+        }
+
+        fn cached_records_of(
+            population: &FetchedPlayerData,
+            uuid: PlayerUuid,
+        ) -> Option<PlayerRecords> {
+            let records = population.data.records_of(uuid);
+            if records.is_empty() {
+                return None;
+            }
+
+            let counts = PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| population.has_kind(*kind))
+                .filter_map(|kind| {
+                    let count = records
+                        .iter()
+                        .filter(|data| !data.is_implausible(kind))
+                        .map(|data| data.get(kind))
+                        .reduce(u64::saturating_add)?;
+                    Some((kind, count))
+                })
+                .collect();
+
+            Some(PlayerRecords {
+                counts,
+                last_seen: records.iter().filter_map(|data| data.last_seen).max(),
+                name: records.iter().find_map(|data| data.name.clone()),
+                season: population.season.clone(),
+            })
+        }
+
+        /// Looks the player up in the repository, checking the values against the plausibility
+        /// bounds like a refresh does.
+        async fn fetch_records_of(
+            &self,
+            uuid: &PlayerUuid,
+        ) -> anyhow::Result<Option<PlayerRecords>> {
+            let counts = future::try_join_all(
+                PlayerDataKind::ALL
+                    .into_iter()
+                    .filter(|kind| !self.fetch_config.is_disabled(*kind))
+                    .map(|kind| async move {
+                        let count = self.repository.get_count_for(uuid, kind).await?;
+                        anyhow::Ok(count.map(|count| (kind, count)))
+                    }),
+            );
+            let last_seen = async {
+                if self.fetch_config.fetch_last_seen {
+                    self.repository.get_last_seen_for(uuid).await
+                } else {
+                    Ok(None)
+                }
+            };
+            let name = async {
+                if self.fetch_config.fetch_player_names {
+                    self.repository.get_name_for(uuid).await
+                } else {
+                    Ok(None)
+                }
+            };
+            let (records, season) = future::join(
+                future::try_join3(counts, last_seen, name),
+                current_season(self.repository.as_ref(), &self.fetch_config),
+            )
+            .await;
+            let (counts, last_seen, name) = records?;
+
+            let counts = counts.into_iter().flatten().collect::<Vec<_>>();
+            if counts.is_empty() {
+                return Ok(None);
+            }
+            let bounds = self
+                .fetch_config
+                .plausibility_bounds()
+                .as_of(SystemTime::now());
+
+            Ok(Some(PlayerRecords {
+                counts: counts
+                    .into_iter()
+                    .filter(|(kind, count)| {
+                        bounds[kind.index()].is_none_or(|bound| *count <= bound)
+                    })
+                    .collect(),
+                last_seen,
+                name,
+                season,
+            }))
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct GetGroupTotalsUseCase {
+        pub repository: Arc<dyn GroupMembershipRepository>,
+    }
+
+    impl GetGroupTotalsUseCase {
+        /// Totals the statistics in `data` over the members of each group, leaving the
+        /// implausible values out. Members the data holds several records of, one per game
+        /// server, are counted once.
+        #[tracing::instrument(skip_all)]
+        pub async fn get_group_totals(
+            &self,
+            data: &KnownAggregatedPlayerData,
+        ) -> anyhow::Result<GroupTotals> {
+            let memberships = self.repository.get_all_memberships().await?;
+
+            let mut totals = IndexMap::<String, AggregatedGroupData>::new();
+            let mut groups_of = HashMap::<PlayerUuid, Vec<usize>>::new();
+            for (uuid, group) in memberships {
+                let entry = totals.entry(group);
+                let index = entry.index();
+                entry.or_default();
+
+                let groups = groups_of.entry(uuid).or_default();
+                if !groups.contains(&index) {
+                    groups.push(index);
+                }
+            }
+
+            let mut counted_members = HashSet::new();
+            for (player, values) in &data.0 {
+                let Some(groups) = groups_of.get(&player.uuid) else {
+                    continue;
+                };
+                let first_record = counted_members.insert(player.uuid);
+
+                for index in groups {
+                    let total = &mut totals[*index];
+                    if first_record {
+                        total.member_count += 1;
+                    }
+                    for kind in PlayerDataKind::ALL
+                        .into_iter()
+                        .filter(|kind| !values.is_implausible(*kind))
+                    {
+                        total.values[kind.index()] =
+                            total.get(kind).saturating_add(values.get(kind));
+                    }
+                }
+            }
+
+            Ok(GroupTotals(totals))
+        }
+    }
+
+    /// Summarizes the fetched data over the players, for the totals metrics and the summary API.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct GetPopulationSummaryUseCase;
+
+    impl GetPopulationSummaryUseCase {
+        #[tracing::instrument(skip_all)]
+        pub fn get_population_summary(&self, fetched: &FetchedPlayerData) -> PopulationSummary {
+            let data = &fetched.data.0;
+            let player_count = data
+                .keys()
+                .map(|player| player.uuid)
+                .collect::<HashSet<_>>()
+                .len() as u64;
+
+            let mut values = Vec::with_capacity(data.len());
+            let kinds = PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| fetched.has_kind(*kind))
+                .map(|kind| {
+                    values.clear();
+                    values.extend(
+                        data.values()
+                            .filter(|data| !data.is_implausible(kind))
+                            .map(|data| data.get(kind)),
+                    );
+
+                    (kind, KindSummary::of(&mut values))
+                })
+                .collect();
+
+            PopulationSummary {
+                player_count,
+                kinds,
+            }
+        }
+    }
+
+    /// Holds the data fetched by the previous refresh so that per-refresh deltas can be computed.
+    #[derive(Debug, Default)]
+    pub struct PreviousSnapshotStore {
+        previous: Mutex<Option<Arc<FetchedPlayerData>>>,
+    }
+
+    impl PreviousSnapshotStore {
+        /// Records `current` as the latest data and returns its delta against the one recorded
+        /// before, if any.
+        #[tracing::instrument(skip_all)]
+        pub fn replace_and_compute_delta(
+            &self,
+            current: Arc<FetchedPlayerData>,
+        ) -> Option<KnownAggregatedPlayerDataDelta> {
+            let previous = self
+                .previous
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .replace(current.clone());
+
+            previous.map(|previous| current.data.delta_since(&previous.data))
+        }
+
+        pub fn previous(&self) -> Option<Arc<FetchedPlayerData>> {
+            self.previous
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+        }
+
+        /// Forgets the recorded data, so that the next refresh has no deltas.
+        pub fn clear(&self) {
+            self.previous
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+        }
+    }
+
+    /// Player data as of a refresh, together with its deltas against the previous refresh.
+    #[derive(Debug)]
+    pub struct PlayerDataSnapshot {
+        /// Identifies the snapshot among the ones taken since startup.
+        pub id: u64,
+        pub fetched: Arc<FetchedPlayerData>,
+        pub deltas: Option<KnownAggregatedPlayerDataDelta>,
+        /// Totals of each group of players. `None` unless group memberships are configured and
+        /// were fetched along with the snapshot.
+        pub group_totals: Option<GroupTotals>,
+        pub summary: PopulationSummary,
+        pub standings: Arc<PopulationStandings>,
+        pub taken_at: Instant,
+        /// Whether the snapshot was restored from a previous run rather than fetched.
+        pub restored: bool,
+    }
+
+    impl PlayerDataSnapshot {
+        pub fn approximate_size_bytes(&self) -> usize {
+            self.fetched.data.approximate_size_bytes()
+                + self.standings.approximate_size_bytes()
+                + self
+                    .deltas
+                    .as_ref()
+                    .map_or(0, KnownAggregatedPlayerDataDelta::approximate_size_bytes)
+        }
+    }
+
+    #[derive(Debug)]
+    struct SuppressedErrors {
+        since: Instant,
+        count: u64,
+    }
+
+    /// Log of errors that keeps a failing upstream from flooding it.
+    ///
+    /// Only the first of identical errors is logged, and the ones following it within the
+    /// summary interval are counted instead. The count is logged once the interval is over, by
+    /// the next error or by [`RepeatedErrorLog::spawn_periodic_flush`], whichever comes first.
+    #[derive(Debug)]
+    pub struct RepeatedErrorLog {
+        summary_interval: Duration,
+        suppressed: Mutex<HashMap<String, SuppressedErrors>>,
+    }
+
+    impl RepeatedErrorLog {
+        #[must_use]
+        pub fn new(summary_interval: Duration) -> Self {
+            Self {
+                summary_interval,
+                suppressed: Mutex::default(),
+            }
+        }
+
+        /// Spawns a task that logs the counts of the intervals that are over, so that they are
+        /// not held back until the upstream fails again.
+        pub fn spawn_periodic_flush(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+            tokio::spawn(async move {
+                let mut ticks = tokio::time::interval(self.summary_interval);
+                ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+                loop {
+                    ticks.tick().await;
+                    self.flush(Instant::now());
+                }
+            })
+        }
+
+        /// Logs and forgets the counts of the intervals that are over at `now`.
+        fn flush(&self, now: Instant) {
+            let summaries = Self::take_expired(
+                &mut self
+                    .suppressed
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+                now,
+                self.summary_interval,
+            );
+            self.log_summaries(summaries);
+        }
+
+        fn take_expired(
+            suppressed: &mut HashMap<String, SuppressedErrors>,
+            now: Instant,
+            summary_interval: Duration,
+        ) -> Vec<(String, u64)> {
+            let mut summaries = Vec::new();
+            suppressed.retain(|key, errors| {
+                if now.duration_since(errors.since) < summary_interval {
+                    return true;
+                }
+                if errors.count > 0 {
+                    summaries.push((key.clone(), errors.count));
+                }
+                false
+            });
+
+            summaries
+        }
+
+        fn log_summaries(&self, summaries: Vec<(String, u64)>) {
+            for (key, count) in summaries {
+                tracing::error!(
+                    "suppressed {} identical errors in the last {:?}: {}",
+                    count,
+                    self.summary_interval,
+                    key
+                );
+            }
+        }
+
+        pub fn error(&self, context: &str, error: &anyhow::Error) {
+            let key = format!("{context}: {error:#}");
+            let now = Instant::now();
+
+            let mut suppressed = self
+                .suppressed
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let summaries = Self::take_expired(&mut suppressed, now, self.summary_interval);
+            let first = match suppressed.entry(key) {
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().count += 1;
+                    false
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(SuppressedErrors {
+                        since: now,
+                        count: 0,
+                    });
+                    true
+                }
+            };
+            drop(suppressed);
+
+            self.log_summaries(summaries);
+            if first {
+                tracing::error!("{}: {:?}", context, error);
+            }
+        }
+    }
+
+    /// Tracks the [`HealthState`], transitioned by the outcome of refreshes and by repositories
+    /// serving results they fetched earlier in place of the upstream.
+    #[derive(Debug)]
+    pub struct HealthTracker {
+        state: Mutex<HealthState>,
+        cached_results_served: AtomicBool,
+    }
+
+    impl Default for HealthTracker {
+        fn default() -> Self {
+            Self {
+                state: Mutex::new(HealthState::Startup),
+                cached_results_served: AtomicBool::new(false),
+            }
+        }
+    }
+
+    impl HealthTracker {
+        pub fn state(&self) -> HealthState {
+            *self.state.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        fn transition(&self, to: HealthState) {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            if *state != to {
+                tracing::info!(
+                    "health state changed from {} to {}",
+                    state.as_str(),
+                    to.as_str()
+                );
+                *state = to;
+            }
+        }
+
+        /// Notes that a repository served results it had fetched earlier instead of querying
+        /// the upstream, which makes the ongoing refresh stale.
+        pub fn record_cached_results_served(&self) {
+            self.cached_results_served.store(true, Ordering::Release);
+        }
+
+        fn record_snapshot_restored(&self) {
+            self.transition(HealthState::DegradedServingStale);
+        }
+
+        fn begin_refresh(&self) {
+            self.cached_results_served.store(false, Ordering::Release);
+        }
+
+        fn record_refresh_succeeded(&self) {
+            self.transition(if self.cached_results_served.load(Ordering::Acquire) {
+                HealthState::DegradedServingStale
+            } else {
+                HealthState::Healthy
+            });
+        }
+
+        fn record_refresh_failed(&self, earlier_data_available: bool) {
+            self.transition(if earlier_data_available {
+                HealthState::DegradedServingStale
+            } else {
+                HealthState::UpstreamDown
+            });
+        }
+    }
+
+    /// Error of a refresh whose result is shared by every caller waiting for it.
+    #[derive(Debug, Clone)]
+    pub struct SharedRefreshError(Arc<anyhow::Error>);
+
+    impl std::fmt::Display for SharedRefreshError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "shared refresh failed")
+        }
+    }
+
+    impl std::error::Error for SharedRefreshError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(self.0.as_ref().as_ref())
+        }
+    }
+
+    type SharedRefresh =
+        Shared<BoxFuture<'static, Result<Arc<PlayerDataSnapshot>, SharedRefreshError>>>;
+
+    #[derive(Default)]
+    pub struct SnapshotCache {
+        latest: Mutex<Option<Arc<PlayerDataSnapshot>>>,
+        background_refresh_running: AtomicBool,
+        in_flight_refresh: Mutex<Option<SharedRefresh>>,
+        next_snapshot_id: AtomicU64,
+    }
+
+    impl std::fmt::Debug for SnapshotCache {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("SnapshotCache")
+                .field("latest", &self.latest)
+                .field(
+                    "background_refresh_running",
+                    &self.background_refresh_running,
+                )
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl SnapshotCache {
+        pub fn latest(&self) -> Option<Arc<PlayerDataSnapshot>> {
+            self.latest
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+        }
+
+        /// The latest snapshot, if it would be served without waiting for a refresh.
+        pub fn fresh(&self, cache_config: &config::CacheConfig) -> Option<Arc<PlayerDataSnapshot>> {
+            self.latest().filter(|snapshot| {
+                snapshot.restored
+                    || snapshot.taken_at.elapsed()
+                        < cache_config.cache_ttl() + cache_config.stale_while_revalidate()
+            })
+        }
+
+        pub fn store(&self, snapshot: Arc<PlayerDataSnapshot>) {
+            *self.latest.lock().unwrap_or_else(PoisonError::into_inner) = Some(snapshot);
+        }
+
+        fn next_snapshot_id(&self) -> u64 {
+            self.next_snapshot_id.fetch_add(1, Ordering::Relaxed)
+        }
+
+        /// Returns `true` if the caller is now responsible for the only background refresh.
+        fn try_begin_background_refresh(&self) -> bool {
+            !self.background_refresh_running.swap(true, Ordering::AcqRel)
+        }
+
+        fn end_background_refresh(&self) {
+            self.background_refresh_running
+                .store(false, Ordering::Release);
+        }
+    }
+
+    /// Reports the values that `fetched` quarantines but `previous` did not, so that a value is
+    /// reported once rather than on every refresh.
+    fn report_newly_quarantined(fetched: &FetchedPlayerData, previous: Option<&FetchedPlayerData>) {
+        for (player, data) in &fetched.data.0 {
+            let previous = previous.and_then(|previous| previous.data.0.get(player));
+            for kind in PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| data.is_implausible(*kind))
+                .filter(|kind| !previous.is_some_and(|previous| previous.is_implausible(*kind)))
+            {
+                tracing::warn!(
+                    uuid = %player.uuid,
+                    kind = kind.as_str(),
+                    value = data.get(kind),
+                    "quarantined an implausible value"
+                );
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct GetPlayerDataSnapshotUseCase {
+        pub get_all_player_data: GetAllPlayerDataUseCase,
+        pub previous_snapshot: Arc<PreviousSnapshotStore>,
+        pub snapshot_cache: Arc<SnapshotCache>,
+        pub cache_config: config::CacheConfig,
+        pub snapshot_persistence: Option<Arc<dyn SnapshotPersistence>>,
+        pub get_group_totals: Option<GetGroupTotalsUseCase>,
+        pub error_log: Arc<RepeatedErrorLog>,
+        pub health: Arc<HealthTracker>,
+    }
+
+    impl GetPlayerDataSnapshotUseCase {
+        /// Loads the data saved by a previous run into the cache, to be served until the first
+        /// refresh completes. Returns whether anything was restored.
+        #[tracing::instrument(skip_all)]
+        pub fn restore_persisted_snapshot(&self) -> anyhow::Result<bool> {
+            let Some(persistence) = &self.snapshot_persistence else {
+                return Ok(false);
+            };
+            let Some(fetched) = persistence.load()? else {
+                return Ok(false);
+            };
+            let fetched = Arc::new(fetched);
+
+            // Deltas of the first refresh are computed against the restored data
+            if self.get_all_player_data.derived_stats_config.expose_deltas && fetched.is_complete()
+            {
+                self.previous_snapshot
+                    .replace_and_compute_delta(fetched.clone());
+            }
+
+            self.snapshot_cache.store(Arc::new(PlayerDataSnapshot {
+                id: self.snapshot_cache.next_snapshot_id(),
+                summary: GetPopulationSummaryUseCase.get_population_summary(&fetched),
+                standings: Arc::new(PopulationStandings::of(&fetched)),
+                fetched,
+                deltas: None,
+                group_totals: None,
+                taken_at: Instant::now(),
+                restored: true,
+            }));
+            self.health.record_snapshot_restored();
+
+            Ok(true)
+        }
+
+        fn spawn_persist(&self, fetched: Arc<FetchedPlayerData>) {
+            if let Some(persistence) = self.snapshot_persistence.clone() {
+                tokio::task::spawn_blocking(move || {
+                    if let Err(e) = persistence.save(&fetched) {
+                        tracing::error!("failed to persist snapshot: {:?}", e);
+                    }
+                });
+            }
+        }
+
+        /// Returns the cached snapshot if it is younger than the configured TTL. Snapshots that
+        /// have expired but are still within the stale-while-revalidate window are returned as
+        /// well, while a refresh runs in the background. Otherwise the snapshot is refreshed
+        /// from the repository before returning.
+        #[tracing::instrument(skip_all)]
+        pub async fn get_snapshot(&self) -> anyhow::Result<Arc<PlayerDataSnapshot>> {
+            if let Some(snapshot) = self.snapshot_cache.latest() {
+                if snapshot.restored {
+                    self.spawn_background_refresh();
+                    return Ok(snapshot);
+                }
+
+                let age = snapshot.taken_at.elapsed();
+
+                if age < self.cache_config.cache_ttl() {
+                    return Ok(snapshot);
+                }
+
+                if age < self.cache_config.cache_ttl() + self.cache_config.stale_while_revalidate()
+                {
+                    self.spawn_background_refresh();
+                    return Ok(snapshot);
+                }
+            }
+
+            self.refresh_and_store().await
+        }
+
+        fn spawn_background_refresh(&self) {
+            if !self.snapshot_cache.try_begin_background_refresh() {
+                return;
+            }
+
+            let use_case = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = use_case.refresh_and_store().await {
+                    use_case.error_log.error("background refresh failed", &e);
+                }
+                use_case.snapshot_cache.end_background_refresh();
+            });
+        }
+
+        /// Refreshes the snapshot, joining the refresh that is already in flight if there is
+        /// one so that concurrent callers do not each query the repository.
+        async fn refresh_and_store(&self) -> anyhow::Result<Arc<PlayerDataSnapshot>> {
+            let refresh = {
+                let mut in_flight_refresh = self
+                    .snapshot_cache
+                    .in_flight_refresh
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+
+                in_flight_refresh
+                    .get_or_insert_with(|| {
+                        let use_case = self.clone();
+
+                        async move {
+                            use_case.health.begin_refresh();
+                            let result = use_case.refresh().await.map(Arc::new);
+                            match &result {
+                                Ok(snapshot) => {
+                                    use_case.snapshot_cache.store(snapshot.clone());
+                                    use_case.health.record_refresh_succeeded();
+                                }
+                                Err(_) => use_case.health.record_refresh_failed(
+                                    use_case.snapshot_cache.latest().is_some(),
+                                ),
+                            }
+                            use_case
+                                .snapshot_cache
+                                .in_flight_refresh
+                                .lock()
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .take();
+
+                            result.map_err(|e| SharedRefreshError(Arc::new(e)))
+                        }
+                        .boxed()
+                        .shared()
+                    })
+                    .clone()
+            };
+
+            Ok(refresh.await?)
+        }
+
+        async fn refresh(&self) -> anyhow::Result<PlayerDataSnapshot> {
+            let fetched = Arc::new(
+                self.get_all_player_data
+                    .get_all_known_aggregated_player_data()
+                    .await?,
+            );
+            report_newly_quarantined(
+                &fetched,
+                self.snapshot_cache
+                    .latest()
+                    .as_deref()
+                    .map(|snapshot| snapshot.fetched.as_ref()),
+            );
+
+            // Deltas against partially fetched data would be meaningless, so partial
+            // snapshots neither produce deltas nor replace the previous snapshot
+            let deltas = if self.get_all_player_data.derived_stats_config.expose_deltas
+                && fetched.is_complete()
+            {
+                self.previous_snapshot
+                    .replace_and_compute_delta(fetched.clone())
+            } else {
+                None
+            };
+
+            if fetched.is_complete() {
+                self.spawn_persist(fetched.clone());
+            }
+
+            // Group totals are an addition to the player data, which is served without them
+            // rather than not at all when the memberships cannot be fetched
+            let group_totals = match &self.get_group_totals {
+                Some(use_case) => match use_case.get_group_totals(&fetched.data).await {
+                    Ok(group_totals) => Some(group_totals),
+                    Err(e) => {
+                        self.error_log.error("failed to total the groups", &e);
+                        None
+                    }
+                },
+                None => None,
+            };
+
+            Ok(PlayerDataSnapshot {
+                id: self.snapshot_cache.next_snapshot_id(),
+                summary: GetPopulationSummaryUseCase.get_population_summary(&fetched),
+                standings: Arc::new(PopulationStandings::of(&fetched)),
+                fetched,
+                deltas,
+                group_totals,
+                taken_at: Instant::now(),
+                restored: false,
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::config::ConfigSources;
+        use crate::domain::{PlayerDataStream, UpstreamConnectionState};
+
+        fn uuid(index: u8) -> PlayerUuid {
+            let mut bytes = [0; 16];
+            bytes[15] = index;
+            PlayerUuid::from_bytes(bytes)
+        }
+
+        /// Serves the same records and makes every player a member of `guild`.
+        #[derive(Debug)]
+        struct FixedRepository(Vec<(PlayerUuid, PlayerDataKind, u64)>);
+
+        #[async_trait::async_trait]
+        impl PlayerDataRepository for FixedRepository {
+            fn get_all_of(&self, kind: PlayerDataKind) -> PlayerDataStream<'_, (Player, u64)> {
+                stream::iter(
+                    self.0
+                        .iter()
+                        .filter(move |(_, recorded_kind, _)| *recorded_kind == kind)
+                        .map(|(uuid, _, value)| Ok((Player::new(*uuid), *value))),
+                )
+                .boxed()
+            }
+
+            fn get_all_last_seen(&self) -> PlayerDataStream<'_, (Player, SystemTime)> {
+                stream::empty().boxed()
+            }
+
+            async fn check_readiness(&self) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            fn connection_state(&self) -> UpstreamConnectionState {
+                UpstreamConnectionState::Ready
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl GroupMembershipRepository for FixedRepository {
+            async fn get_all_memberships(&self) -> anyhow::Result<Vec<(PlayerUuid, String)>> {
+                Ok(self
+                    .0
+                    .iter()
+                    .map(|(uuid, _, _)| (*uuid, "guild".to_string()))
+                    .collect())
+            }
+        }
+
+        fn fold_duplicates(policy: DuplicateRecordPolicy) -> Aggregation {
+            let player = Player::new(uuid(1));
+            let mut aggregation = Aggregation::default();
+            aggregation.fold(
+                vec![
+                    (PlayerDataKind::BreakCount, player, 10, None),
+                    (PlayerDataKind::BreakCount, player, 30, None),
+                    (PlayerDataKind::BreakCount, player, 20, None),
+                    (PlayerDataKind::BuildCount, player, 5, None),
+                ],
+                policy,
+            );
+
+            aggregation
+        }
+
+        #[test]
+        fn resolve_duplicate_records_by_their_largest_value() {
+            let aggregation = fold_duplicates(DuplicateRecordPolicy::Max);
+
+            let data = &aggregation.result_map[&Player::new(uuid(1))];
+            assert_eq!(data.get(PlayerDataKind::BreakCount), 30);
+            assert_eq!(data.get(PlayerDataKind::BuildCount), 5);
+            assert_eq!(
+                aggregation.duplicate_records,
+                HashMap::from([(PlayerDataKind::BreakCount, 2)])
+            );
+        }
+
+        #[test]
+        fn resolve_duplicate_records_by_their_last_value() {
+            let aggregation = fold_duplicates(DuplicateRecordPolicy::Last);
+
+            let data = &aggregation.result_map[&Player::new(uuid(1))];
+            assert_eq!(data.get(PlayerDataKind::BreakCount), 20);
+            assert_eq!(
+                aggregation.duplicate_records,
+                HashMap::from([(PlayerDataKind::BreakCount, 2)])
+            );
+        }
+
+        #[test]
+        fn take_the_names_that_come_along_with_the_records() {
+            let player = Player::new(uuid(1));
+            let mut aggregation = Aggregation::default();
+            aggregation.fold(
+                vec![
+                    (PlayerDataKind::BreakCount, player, 10, None),
+                    (
+                        PlayerDataKind::PlayTicks,
+                        player,
+                        20,
+                        Some("name".to_string()),
+                    ),
+                ],
+                DuplicateRecordPolicy::Max,
+            );
+
+            assert_eq!(
+                aggregation.result_map[&player].name.as_deref(),
+                Some("name")
+            );
+        }
+
+        #[test]
+        fn bound_the_play_ticks_by_the_time_since_the_statistics_were_counted() {
+            let sources = ConfigSources::from_values([
+                (
+                    "PLAUSIBILITY_BOUNDS".to_string(),
+                    "break_count=100,play_ticks=1000000".to_string(),
+                ),
+                (
+                    "STATISTICS_COUNTED_SINCE".to_string(),
+                    "2024-01-01T00:00:00Z".to_string(),
+                ),
+            ]);
+            let config = config::FetchConfig::load(&sources).unwrap();
+
+            let counted_since = humantime::parse_rfc3339("2024-01-01T00:00:00Z").unwrap();
+            let bounds = config
+                .plausibility_bounds()
+                .as_of(counted_since + Duration::from_secs(90));
+
+            assert_eq!(bounds[PlayerDataKind::BreakCount.index()], Some(100));
+            assert_eq!(bounds[PlayerDataKind::PlayTicks.index()], Some(1_800));
+            assert_eq!(bounds[PlayerDataKind::AfkTicks.index()], Some(1_800));
+            assert_eq!(bounds[PlayerDataKind::BuildCount.index()], None);
+        }
+
+        #[test]
+        fn reject_malformed_plausibility_bounds() {
+            let sources = ConfigSources::from_values([(
+                "PLAUSIBILITY_BOUNDS".to_string(),
+                "break_count=many".to_string(),
+            )]);
+
+            let error = config::FetchConfig::load(&sources).unwrap_err();
+
+            assert!(error.to_string().contains("PLAUSIBILITY_BOUNDS"), "{error}");
+        }
+
+        #[tokio::test]
+        async fn leave_quarantined_values_out_of_the_totals_and_the_player_data() {
+            let (honest, cheater) = (uuid(1), uuid(2));
+            let repository = Arc::new(FixedRepository(vec![
+                (honest, PlayerDataKind::BreakCount, 50),
+                (honest, PlayerDataKind::BuildCount, 3),
+                (cheater, PlayerDataKind::BreakCount, 1_000),
+                (cheater, PlayerDataKind::BuildCount, 4),
+            ]));
+            let sources = ConfigSources::from_values([(
+                "PLAUSIBILITY_BOUNDS".to_string(),
+                "break_count=100".to_string(),
+            )]);
+            let derived_stats_config = config::DerivedStatsConfig {
+                compute_ranks: true,
+                ..config::DerivedStatsConfig::default()
+            };
+            let fetch_config = config::FetchConfig::load(&sources).unwrap();
+            let use_case = GetAllPlayerDataUseCase {
+                repository: repository.clone(),
+                derived_stats_config: derived_stats_config.clone(),
+                fetch_config: fetch_config.clone(),
+            };
+
+            let fetched = use_case
+                .get_all_known_aggregated_player_data()
+                .await
+                .unwrap();
+            let totals = GetGroupTotalsUseCase {
+                repository: repository.clone(),
+            }
+            .get_group_totals(&fetched.data)
+            .await
+            .unwrap();
+            let population = Arc::new(fetched);
+            let cheater_stats = GetPlayerDataUseCase {
+                repository,
+                derived_stats_config,
+                fetch_config,
+                cached_snapshot: Some(Arc::new(PlayerDataSnapshot {
+                    id: 0,
+                    summary: GetPopulationSummaryUseCase.get_population_summary(&population),
+                    standings: Arc::new(PopulationStandings::of(&population)),
+                    fetched: population.clone(),
+                    deltas: None,
+                    group_totals: None,
+                    taken_at: Instant::now(),
+                    restored: false,
+                })),
+            }
+            .get_player_data(&cheater)
+            .await
+            .unwrap()
+            .unwrap();
+
+            let cheater_data = &population.data.0[&Player::new(cheater)];
+            assert!(cheater_data.is_implausible(PlayerDataKind::BreakCount));
+            assert!(!cheater_data.is_implausible(PlayerDataKind::BuildCount));
+            assert_eq!(cheater_data.rank(PlayerDataKind::BreakCount), None);
+
+            let guild = &totals.0["guild"];
+            assert_eq!(guild.member_count, 2);
+            assert_eq!(guild.get(PlayerDataKind::BreakCount), 50);
+            assert_eq!(guild.get(PlayerDataKind::BuildCount), 7);
+
+            assert!(cheater_stats
+                .counts
+                .iter()
+                .all(|(kind, _)| *kind != PlayerDataKind::BreakCount));
+            assert!(cheater_stats
+                .counts
+                .contains(&(PlayerDataKind::BuildCount, 4)));
+            assert!(cheater_stats
+                .ranks
+                .contains(&(PlayerDataKind::BuildCount, 1)));
+            assert!(cheater_stats
+                .ranks
+                .iter()
+                .all(|(kind, _)| *kind != PlayerDataKind::BreakCount));
+        }
+
+        #[tokio::test]
+        async fn look_players_up_in_the_repository_without_a_cached_snapshot() {
+            let (honest, cheater) = (uuid(1), uuid(2));
+            let sources = ConfigSources::from_values([(
+                "PLAUSIBILITY_BOUNDS".to_string(),
+                "break_count=100".to_string(),
+            )]);
+            let use_case = GetPlayerDataUseCase {
+                repository: Arc::new(FixedRepository(vec![
+                    (honest, PlayerDataKind::BreakCount, 50),
+                    (cheater, PlayerDataKind::BreakCount, 1_000),
+                    (cheater, PlayerDataKind::BuildCount, 4),
+                ])),
+                derived_stats_config: config::DerivedStatsConfig {
+                    compute_ranks: true,
+                    ..config::DerivedStatsConfig::default()
+                },
+                fetch_config: config::FetchConfig::load(&sources).unwrap(),
+                cached_snapshot: None,
+            };
+
+            let cheater_stats = use_case.get_player_data(&cheater).await.unwrap().unwrap();
+
+            assert_eq!(cheater_stats.counts, vec![(PlayerDataKind::BuildCount, 4)]);
+            assert!(cheater_stats.ranks.is_empty());
+            assert!(use_case.get_player_data(&uuid(3)).await.unwrap().is_none());
+        }
+
+        #[test]
+        fn flush_the_suppressed_errors_once_their_interval_is_over() {
+            let log = RepeatedErrorLog::new(Duration::from_secs(30));
+            let error = anyhow::anyhow!("unreachable");
+            for _ in 0..3 {
+                log.error("failed to fetch", &error);
+            }
+            let mut suppressed = log.suppressed.lock().unwrap();
+            let since = suppressed["failed to fetch: unreachable"].since;
+
+            assert!(RepeatedErrorLog::take_expired(
+                &mut suppressed,
+                since + Duration::from_secs(29),
+                log.summary_interval
+            )
+            .is_empty());
+            assert_eq!(
+                RepeatedErrorLog::take_expired(
+                    &mut suppressed,
+                    since + Duration::from_secs(30),
+                    log.summary_interval
+                ),
+                vec![("failed to fetch: unreachable".to_owned(), 2)]
+            );
+            assert!(suppressed.is_empty());
+            drop(suppressed);
+        }
+    }
+}
+
+pub mod infra_axum_handlers {
+    use crate::domain::{
+        GroupMembershipRepository, HealthState, PlayerDataRepository, PlayerUuid,
+        SnapshotPersistence, UpstreamConnectionState, UpstreamTimeoutError,
+        UpstreamUnavailableError,
+    };
+    use crate::use_cases::config::{CacheConfig, DerivedStatsConfig, FetchConfig};
+    use crate::use_cases::{
+        GetAllPlayerDataUseCase, GetGroupTotalsUseCase, GetPlayerDataSnapshotUseCase,
+        GetPlayerDataUseCase, HealthTracker, PlayerDataSnapshot, PreviousSnapshotStore,
+        RepeatedErrorLog, SnapshotCache,
+    };
+    use arc_swap::{ArcSwap, ArcSwapOption};
+    use axum::body::{self, Bytes, StreamBody};
+    use axum::extract::{ConnectInfo, MatchedPath, Path};
+    use axum::handler::Handler;
+    use axum::http::{header, HeaderMap, Request, StatusCode};
+    use axum::middleware::Next;
+    use axum::response::{IntoResponse, Response};
+    use axum::Json;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use futures::stream::{self, Stream};
+    use ipnet::IpNet;
+    use prometheus::core::{Collector, Desc};
+    use prometheus::proto::MetricFamily;
+    use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts};
+    use std::convert::Infallible;
+    use std::io::Write;
+    use std::net::{IpAddr, SocketAddr};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+    use std::time::{Duration, Instant};
+    use tokio::signal::unix::{signal, SignalKind};
+    use tokio::time::MissedTickBehavior;
+
+    #[derive(Clone, Debug)]
+    pub struct SharedAppState {
+        pub repository: Arc<dyn PlayerDataRepository>,
+        pub group_membership_repository: Option<Arc<dyn GroupMembershipRepository>>,
+        pub derived_stats_config: DerivedStatsConfig,
+        pub reloadable_config: Arc<ArcSwap<ReloadableConfig>>,
+        pub config_reloader: Arc<dyn ConfigReloader>,
+        pub previous_snapshot: Arc<PreviousSnapshotStore>,
+        pub snapshot_cache: Arc<SnapshotCache>,
+        /// Metrics about the translator itself, exposed separately from the player data.
+        pub self_metrics: prometheus::Registry,
+        pub rendering_config: config::RenderingConfig,
+        /// Replaces the UUIDs on `/metrics` when pseudonymization is configured.
+        pub uuid_pseudonymizer: Option<Arc<presenter::UuidPseudonymizer>>,
+        pub rendered_exposition: Arc<RenderedExpositionCache>,
+        pub snapshot_persistence: Option<Arc<dyn SnapshotPersistence>>,
+        pub cache_memory_telemetry: CacheMemoryTelemetry,
+        pub error_log: Arc<RepeatedErrorLog>,
+        pub health: Arc<HealthTracker>,
+    }
+
+    impl SharedAppState {
+        /// How the records are presented on `/metrics`.
+        fn record_presentation(&self) -> presenter::RecordPresentation<'_> {
+            presenter::RecordPresentation {
+                uuid_pseudonymizer: self.uuid_pseudonymizer.as_deref(),
+                rank_series_top_n: self.derived_stats_config.rank_series_top_n,
+                player_names: self.rendering_config.player_name_label,
+            }
+        }
+    }
+
+    /// Settings that take effect without a restart, replaced when the configuration is reloaded.
+    #[derive(Debug, Clone)]
+    pub struct ReloadableConfig {
+        pub fetch_config: FetchConfig,
+        pub cache_config: CacheConfig,
+        pub slow_scrape_config: config::SlowScrapeConfig,
+    }
+
+    /// Reads the configuration again. Settings outside of [`ReloadableConfig`] that can be
+    /// changed while running, such as the log filter, are applied by the reloader itself.
+    pub trait ConfigReloader: std::fmt::Debug + Send + Sync {
+        #[allow(clippy::missing_errors_doc)]
+        fn reload(&self) -> anyhow::Result<ReloadableConfig>;
+    }
+
+    /// Reloads the configuration. Requests that are already being served finish with the
+    /// settings they started with.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn reload_config(state: &SharedAppState) -> anyhow::Result<()> {
+        let config = state.config_reloader.reload()?;
+        state.reloadable_config.store(Arc::new(config));
+        // Applies a changed memory budget right away, warning if the snapshot exceeds it
+        enforce_cache_memory_budget(state);
+
+        Ok(())
+    }
+
+    /// Spawns a task that reloads the configuration whenever the process receives SIGHUP.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn spawn_reload_on_hangup(
+        state: SharedAppState,
+    ) -> anyhow::Result<tokio::task::JoinHandle<()>> {
+        let mut hangups = signal(SignalKind::hangup())?;
+
+        Ok(tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                if let Err(e) = reload_config(&state) {
+                    tracing::error!("failed to reload the configuration: {:?}", e);
+                }
+            }
+        }))
+    }
+
+    /// Exposition text rendered from a snapshot, along with its gzip-compressed form once a
+    /// client has asked for it. Both are kept as chunks rather than one contiguous buffer.
+    #[derive(Debug)]
+    pub struct RenderedExposition {
+        snapshot_id: u64,
+        connection_state: UpstreamConnectionState,
+        chunks: Vec<Bytes>,
+        gzipped: Mutex<Option<Vec<Bytes>>>,
+    }
+
+    impl RenderedExposition {
+        fn gzipped(&self) -> anyhow::Result<Vec<Bytes>> {
+            // The lock is held while compressing so that concurrent scrapes compress only once
+            let mut gzipped = self.gzipped.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(gzipped) = &*gzipped {
+                return Ok(gzipped.clone());
+            }
+
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            let mut gzipped_chunks = Vec::with_capacity(self.chunks.len() + 1);
+            for chunk in &self.chunks {
+                encoder.write_all(chunk)?;
+                // Whatever has been compressed so far is moved out, so that the compressed
+                // form is not accumulated in a single buffer either
+                if !encoder.get_ref().is_empty() {
+                    gzipped_chunks.push(Bytes::from(std::mem::take(encoder.get_mut())));
+                }
+            }
+            gzipped_chunks.push(Bytes::from(encoder.finish()?));
+
+            Ok(gzipped.insert(gzipped_chunks).clone())
+        }
+
+        fn text_size_bytes(&self) -> usize {
+            self.chunks.iter().map(Bytes::len).sum()
+        }
+
+        fn gzipped_size_bytes(&self) -> usize {
+            self.gzipped
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_ref()
+                .map_or(0, |chunks| chunks.iter().map(Bytes::len).sum())
+        }
+
+        fn clear_gzipped(&self) {
+            self.gzipped
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+        }
+    }
+
+    /// Keeps the exposition of the latest snapshot and the records of each player in it.
+    ///
+    /// Scrapes served from the same snapshot skip formatting it again, and rendering a new
+    /// snapshot only formats the players whose values changed.
+    #[derive(Debug, Default)]
+    pub struct RenderedExpositionCache {
+        latest: ArcSwapOption<RenderedExposition>,
+        record_fragments: Mutex<presenter::PlayerRecordFragments>,
+    }
+
+    impl RenderedExpositionCache {
+        pub fn latest(&self) -> Option<Arc<RenderedExposition>> {
+            self.latest.load_full()
+        }
+
+        fn clear(&self) {
+            self.latest.store(None);
+        }
+
+        fn record_fragments(&self) -> MutexGuard<'_, presenter::PlayerRecordFragments> {
+            self.record_fragments
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+        }
+
+        /// The connection state is part of the exposition, so a change of it invalidates the
+        /// cached text as well.
+        fn get_or_render(
+            &self,
+            snapshot: &PlayerDataSnapshot,
+            connection_state: UpstreamConnectionState,
+            record_presentation: presenter::RecordPresentation<'_>,
+        ) -> anyhow::Result<Arc<RenderedExposition>> {
+            let latest_if_current = || {
+                self.latest().filter(|rendered| {
+                    rendered.snapshot_id == snapshot.id
+                        && rendered.connection_state == connection_state
+                })
+            };
+            if let Some(rendered) = latest_if_current() {
+                return Ok(rendered);
+            }
+
+            // Renders are serialized by the fragments, so concurrent scrapes of a new snapshot
+            // render it only once
+            let mut record_fragments = self.record_fragments();
+            if let Some(rendered) = latest_if_current() {
+                return Ok(rendered);
+            }
+
+            let rendered = Arc::new(RenderedExposition {
+                snapshot_id: snapshot.id,
+                connection_state,
+                chunks: presenter::present_player_data_as_prometheus_metrics(
+                    snapshot,
+                    connection_state,
+                    record_presentation,
+                    &mut record_fragments,
+                )?,
+                gzipped: Mutex::default(),
+            });
+            self.latest.store(Some(rendered.clone()));
+            drop(record_fragments);
+
+            Ok(rendered)
+        }
+    }
+
+    /// Kinds of cached artifacts that may be dropped when the cache exceeds its memory budget, in
+    /// the order they are dropped: data older than the latest snapshot first, then what can be
+    /// derived again from it. The latest snapshot itself is never dropped, as every request would
+    /// then fetch it from the upstream again.
+    #[derive(Debug, Clone, Copy)]
+    enum CacheArtifact {
+        PreviousSnapshot,
+        RecordFragments,
+        GzippedExposition,
+        Exposition,
+    }
+
+    impl CacheArtifact {
+        const EVICTION_ORDER: [Self; 4] = [
+            Self::PreviousSnapshot,
+            Self::RecordFragments,
+            Self::GzippedExposition,
+            Self::Exposition,
+        ];
+
+        const fn as_str(self) -> &'static str {
+            match self {
+                Self::PreviousSnapshot => "previous_snapshot",
+                Self::RecordFragments => "record_fragments",
+                Self::GzippedExposition => "gzipped_exposition",
+                Self::Exposition => "exposition",
+            }
+        }
+    }
+
+    /// Self-metrics about the memory taken up by cached artifacts.
+    #[derive(Debug, Clone)]
+    pub struct CacheMemoryTelemetry {
+        memory_bytes: IntGaugeVec,
+        evictions: IntCounterVec,
+        /// Whether it has been warned that the latest snapshot alone exceeds the budget, so that
+        /// it is warned once rather than on every request.
+        snapshot_exceeds_budget: Arc<AtomicBool>,
+    }
+
+    impl CacheMemoryTelemetry {
+        #[allow(clippy::missing_errors_doc)]
+        pub fn register(registry: &prometheus::Registry) -> anyhow::Result<Self> {
+            let memory_bytes = IntGaugeVec::new(
+                Opts::new(
+                    "seichi_translator_cache_memory_bytes",
+                    "Approximate memory taken up by each kind of cached artifact",
+                ),
+                &["artifact"],
+            )?;
+            let evictions = IntCounterVec::new(
+                Opts::new(
+                    "seichi_translator_cache_evictions_total",
+                    "Cached artifacts dropped to keep the cache within its memory budget",
+                ),
+                &["artifact"],
+            )?;
+
+            registry.register(Box::new(memory_bytes.clone()))?;
+            registry.register(Box::new(evictions.clone()))?;
+
+            Ok(Self {
+                memory_bytes,
+                evictions,
+                snapshot_exceeds_budget: Arc::default(),
+            })
+        }
+    }
+
+    const HTTP_REQUEST_DURATION_BUCKETS: [f64; 12] = [
+        0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+    ];
+
+    /// Self-metric exposing the current [`HealthState`] as one series per state, of which only
+    /// the current one is `1`.
+    #[derive(Debug)]
+    pub struct HealthStateCollector {
+        health: Arc<HealthTracker>,
+        states: IntGaugeVec,
+    }
+
+    impl HealthStateCollector {
+        #[allow(clippy::missing_errors_doc)]
+        pub fn register(
+            registry: &prometheus::Registry,
+            health: Arc<HealthTracker>,
+        ) -> anyhow::Result<()> {
+            let states = IntGaugeVec::new(
+                Opts::new(
+                    "seichi_translator_health_state",
+                    "Current health state of the translator",
+                ),
+                &["state"],
+            )?;
+            registry.register(Box::new(Self { health, states }))?;
+
+            Ok(())
+        }
+    }
+
+    impl Collector for HealthStateCollector {
+        fn desc(&self) -> Vec<&Desc> {
+            self.states.desc()
+        }
+
+        fn collect(&self) -> Vec<MetricFamily> {
+            let current = self.health.state();
+            for state in HealthState::ALL {
+                self.states
+                    .with_label_values(&[state.as_str()])
+                    .set(i64::from(state == current));
+            }
+
+            self.states.collect()
+        }
+    }
+
+    /// Self-metrics about the requests served by the HTTP server.
+    #[derive(Debug, Clone)]
+    pub struct HttpTelemetry {
+        requests: IntCounterVec,
+        request_duration: HistogramVec,
+        requests_in_flight: IntGaugeVec,
+    }
+
+    /// Counts a request as in flight until dropped, so that cancelled requests are not left
+    /// counted.
+    struct InFlightRequest(IntGauge);
+
+    impl Drop for InFlightRequest {
+        fn drop(&mut self) {
+            self.0.dec();
+        }
+    }
+
+    impl HttpTelemetry {
+        #[allow(clippy::missing_errors_doc)]
+        pub fn register(registry: &prometheus::Registry) -> anyhow::Result<Self> {
+            let requests = IntCounterVec::new(
+                Opts::new(
+                    "translator_http_requests_total",
+                    "HTTP requests served, by route, method and status code",
+                ),
+                &["route", "method", "status"],
+            )?;
+            let request_duration = HistogramVec::new(
+                HistogramOpts::new(
+                    "translator_http_request_duration_seconds",
+                    "Time taken to respond to HTTP requests, up to the response headers",
+                )
+                .buckets(HTTP_REQUEST_DURATION_BUCKETS.to_vec()),
+                &["route", "method"],
+            )?;
+            let requests_in_flight = IntGaugeVec::new(
+                Opts::new(
+                    "translator_http_requests_in_flight",
+                    "HTTP requests currently being responded to",
+                ),
+                &["route"],
+            )?;
+
+            registry.register(Box::new(requests.clone()))?;
+            registry.register(Box::new(request_duration.clone()))?;
+            registry.register(Box::new(requests_in_flight.clone()))?;
+
+            Ok(Self {
+                requests,
+                request_duration,
+                requests_in_flight,
+            })
+        }
+
+        /// Middleware recording the request it is applied to. Must be applied with
+        /// `route_layer`, as requests are labelled with the route they matched.
+        pub async fn track<B>(self, request: Request<B>, next: Next<B>) -> Response {
+            let route = request
+                .extensions()
+                .get::<MatchedPath>()
+                .map_or("unmatched", MatchedPath::as_str)
+                .to_string();
+            let method = request.method().clone();
+
+            let in_flight = self.requests_in_flight.with_label_values(&[&route]);
+            in_flight.inc();
+            let _in_flight = InFlightRequest(in_flight);
+
+            let started_at = Instant::now();
+            let response = next.run(request).await;
+
+            self.request_duration
+                .with_label_values(&[&route, method.as_str()])
+                .observe(started_at.elapsed().as_secs_f64());
+            self.requests
+                .with_label_values(&[&route, method.as_str(), response.status().as_str()])
+                .inc();
+
+            response
+        }
+    }
+
+    fn cached_size_bytes(state: &SharedAppState, artifact: CacheArtifact) -> usize {
+        match artifact {
+            CacheArtifact::PreviousSnapshot => {
+                let latest = state.snapshot_cache.latest();
+                state
+                    .previous_snapshot
+                    .previous()
+                    // The previous data is usually the latest snapshot itself
+                    .filter(|previous| {
+                        !latest.is_some_and(|latest| Arc::ptr_eq(previous, &latest.fetched))
+                    })
+                    .map_or(0, |previous| previous.data.approximate_size_bytes())
+            }
+            CacheArtifact::RecordFragments => state
+                .rendered_exposition
+                .record_fragments()
+                .approximate_size_bytes(),
+            CacheArtifact::GzippedExposition => state
+                .rendered_exposition
+                .latest()
+                .map_or(0, |rendered| rendered.gzipped_size_bytes()),
+            CacheArtifact::Exposition => state
+                .rendered_exposition
+                .latest()
+                .map_or(0, |rendered| rendered.text_size_bytes()),
+        }
+    }
+
+    fn evict(state: &SharedAppState, artifact: CacheArtifact) {
+        match artifact {
+            CacheArtifact::PreviousSnapshot => state.previous_snapshot.clear(),
+            CacheArtifact::RecordFragments => state.rendered_exposition.record_fragments().clear(),
+            CacheArtifact::GzippedExposition => {
+                if let Some(rendered) = state.rendered_exposition.latest() {
+                    rendered.clear_gzipped();
+                }
+            }
+            CacheArtifact::Exposition => state.rendered_exposition.clear(),
+        }
+    }
+
+    /// Updates the cache memory gauges and, if the cache exceeds its memory budget, drops cached
+    /// artifacts in [`CacheArtifact::EVICTION_ORDER`] until it fits again. Responses being sent
+    /// keep their own references, so evicting only affects subsequent requests.
+    fn enforce_cache_memory_budget(state: &SharedAppState) {
+        let mut sizes = CacheArtifact::EVICTION_ORDER
+            .map(|artifact| (artifact, cached_size_bytes(state, artifact)));
+        let snapshot_size = state
+            .snapshot_cache
+            .latest()
+            .map_or(0, |snapshot| snapshot.approximate_size_bytes());
+
+        let budget = state
+            .reloadable_config
+            .load()
+            .cache_config
+            .cache_memory_budget_bytes;
+        let snapshot_exceeds_budget = budget.is_some_and(|budget| snapshot_size > budget);
+        if snapshot_exceeds_budget
+            && !state
+                .cache_memory_telemetry
+                .snapshot_exceeds_budget
+                .swap(true, Ordering::Relaxed)
+        {
+            tracing::warn!(
+                "the latest snapshot alone takes up {} bytes, more than the cache memory budget \
+                 of {} bytes, which is kept but cannot be met",
+                snapshot_size,
+                budget.unwrap_or_default()
+            );
+        } else if !snapshot_exceeds_budget {
+            state
+                .cache_memory_telemetry
+                .snapshot_exceeds_budget
+                .store(false, Ordering::Relaxed);
+        }
+
+        if let Some(budget) = budget {
+            let mut total = snapshot_size + sizes.iter().map(|(_, size)| size).sum::<usize>();
+
+            for (artifact, size) in &mut sizes {
+                if total <= budget {
+                    break;
+                }
+                if *size == 0 {
+                    continue;
+                }
+
+                tracing::warn!(
+                    "dropping cached {} of {} bytes to fit the memory budget of {} bytes",
+                    artifact.as_str(),
+                    size,
+                    budget
+                );
+                evict(state, *artifact);
+                state
+                    .cache_memory_telemetry
+                    .evictions
+                    .with_label_values(&[artifact.as_str()])
+                    .inc();
+                total -= *size;
+                *size = 0;
+            }
+        }
+
+        for (artifact, size) in sizes
+            .into_iter()
+            .map(|(artifact, size)| (artifact.as_str(), size))
+            .chain([("snapshot", snapshot_size)])
+        {
+            state
+                .cache_memory_telemetry
+                .memory_bytes
+                .with_label_values(&[artifact])
+                .set(i64::try_from(size).unwrap_or(i64::MAX));
+        }
+    }
+
+    pub mod config {
+        use crate::config::{ConfigSources, Settings};
+        use ipnet::IpNet;
+        use std::net::{IpAddr, SocketAddr};
+        use std::time::Duration;
+
+        fn default_listen_address() -> SocketAddr {
+            SocketAddr::from(([0, 0, 0, 0], 80))
+        }
+
+        const fn default_shutdown_grace_period_millis() -> u64 {
+            25_000
+        }
+
+        const fn default_max_request_body_bytes() -> u64 {
+            64 * 1024
+        }
+
+        /// Smallest read buffer hyper accepts, which bounds the size of the headers.
+        const MIN_MAX_HEADER_BYTES: usize = 8192;
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+        pub struct ServerConfig {
+            /// Address the HTTP server listens on.
+            #[serde(default = "default_listen_address")]
+            pub listen_address: SocketAddr,
+            /// Address of a second, plain HTTP listener serving `/healthz`, `/readyz`,
+            /// `/internal/metrics` and `/admin/*` instead of `listen_address`, without the source
+            /// allowlist. Every route is served on `listen_address` when unset.
+            pub internal_listen_address: Option<SocketAddr>,
+            /// How long requests in flight are waited for after SIGTERM or SIGINT, before they
+            /// are dropped. Should be shorter than the grace period of the orchestrator.
+            #[serde(default = "default_shutdown_grace_period_millis")]
+            pub shutdown_grace_period_millis: u64,
+            /// Largest number of connections served at once. Further connections wait in the
+            /// backlog of the listening socket until one is closed. Unlimited when unset.
+            pub max_connections: Option<usize>,
+            /// Largest size of the headers of a request, at least 8192 bytes.
+            pub max_header_bytes: Option<usize>,
+            /// Largest body accepted by the routes taking one. Routes that only answer GET
+            /// reject every body.
+            #[serde(default = "default_max_request_body_bytes")]
+            pub max_request_body_bytes: u64,
+            /// PEM files of the certificate and key served over TLS. Plain HTTP is served when
+            /// unset.
+            pub tls_cert_path: Option<String>,
+            pub tls_key_path: Option<String>,
+            /// PEM file of the CA certificate that clients must present a certificate issued by.
+            /// Client certificates are not requested when unset.
+            pub tls_client_ca_cert_path: Option<String>,
+        }
+
+        impl Settings for ServerConfig {}
+
+        impl ServerConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let config = sources.extract::<Self>()?;
+
+                if config.max_connections == Some(0) {
+                    return Err(sources.invalid("MAX_CONNECTIONS", "must be at least 1"));
+                }
+                if config
+                    .max_header_bytes
+                    .is_some_and(|max_header_bytes| max_header_bytes < MIN_MAX_HEADER_BYTES)
+                {
+                    return Err(sources.invalid(
+                        "MAX_HEADER_BYTES",
+                        format!("must be at least {MIN_MAX_HEADER_BYTES}"),
+                    ));
+                }
+                match (&config.tls_cert_path, &config.tls_key_path) {
+                    (Some(_), None) => {
+                        return Err(sources
+                            .invalid("TLS_KEY_PATH", "is required when TLS_CERT_PATH is set"))
+                    }
+                    (None, Some(_)) => {
+                        return Err(sources
+                            .invalid("TLS_CERT_PATH", "is required when TLS_KEY_PATH is set"))
+                    }
+                    (None, None) if config.tls_client_ca_cert_path.is_some() => {
+                        return Err(sources.invalid(
+                            "TLS_CLIENT_CA_CERT_PATH",
+                            "requires TLS to be served with TLS_CERT_PATH and TLS_KEY_PATH",
+                        ))
+                    }
+                    _ => {}
+                }
+
+                Ok(config)
+            }
+
+            #[allow(clippy::missing_errors_doc)]
+            pub fn tls_acceptor(&self) -> anyhow::Result<Option<tokio_rustls::TlsAcceptor>> {
+                let (Some(cert_path), Some(key_path)) = (&self.tls_cert_path, &self.tls_key_path)
+                else {
+                    return Ok(None);
+                };
+
+                super::listener::tls_acceptor(
+                    cert_path,
+                    key_path,
+                    self.tls_client_ca_cert_path.as_deref(),
+                )
+                .map(Some)
+            }
+
+            #[must_use]
+            pub const fn shutdown_grace_period(&self) -> Duration {
+                Duration::from_millis(self.shutdown_grace_period_millis)
+            }
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        pub struct AuthConfig {
+            /// Token that requests to `/metrics` and `/api/*` must present as
+            /// `Authorization: Bearer <token>`. Those routes are open to anyone when unset.
+            pub http_auth_token: Option<String>,
+            /// User name that scrapers unable to send a token may present with HTTP basic
+            /// authentication instead.
+            pub http_basic_auth_username: Option<String>,
+            /// bcrypt hash of the password of `http_basic_auth_username`.
+            pub http_basic_auth_password_hash: Option<String>,
+        }
+
+        impl Settings for AuthConfig {
+            const SECRETS: &'static [&'static str] =
+                &["http_auth_token", "http_basic_auth_password_hash"];
+        }
+
+        impl AuthConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let config = sources.extract::<Self>()?;
+
+                match (
+                    &config.http_basic_auth_username,
+                    &config.http_basic_auth_password_hash,
+                ) {
+                    (Some(_), None) => {
+                        return Err(sources.invalid(
+                            "HTTP_BASIC_AUTH_PASSWORD_HASH",
+                            "is required when HTTP_BASIC_AUTH_USERNAME is set",
+                        ))
+                    }
+                    (None, Some(_)) => {
+                        return Err(sources.invalid(
+                            "HTTP_BASIC_AUTH_USERNAME",
+                            "is required when HTTP_BASIC_AUTH_PASSWORD_HASH is set",
+                        ))
+                    }
+                    (Some(_), Some(hash)) if hash.parse::<bcrypt::HashParts>().is_err() => {
+                        return Err(sources
+                            .invalid("HTTP_BASIC_AUTH_PASSWORD_HASH", "is not a bcrypt hash"))
+                    }
+                    _ => {}
+                }
+
+                Ok(config)
+            }
+
+            /// User name and bcrypt hash of the password accepted by basic authentication.
+            #[must_use]
+            pub fn basic_credentials(&self) -> Option<(String, String)> {
+                self.http_basic_auth_username
+                    .clone()
+                    .zip(self.http_basic_auth_password_hash.clone())
+            }
+
+            #[must_use]
+            pub fn bearer_token(&self) -> Option<String> {
+                self.http_auth_token
+                    .as_deref()
+                    .map(|token| token.trim().to_string())
+            }
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        pub struct SourceAllowlistConfig {
+            /// Networks that requests are accepted from, as CIDRs or addresses separated by
+            /// commas (e.g. `10.0.0.0/8,192.168.1.5`). Requests from anywhere are accepted when
+            /// empty.
+            #[serde(default)]
+            pub allowed_source_cidrs: Vec<String>,
+            /// Number of reverse proxies in front of the server, trusted to append the address
+            /// they received a request from to `X-Forwarded-For`. The address of the client is
+            /// the one appended by the outermost of them, or the address of the peer if 0.
+            #[serde(default)]
+            pub trusted_proxy_depth: usize,
+        }
+
+        impl Settings for SourceAllowlistConfig {}
+
+        impl SourceAllowlistConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let config = sources.extract::<Self>()?;
+
+                for cidr in config.cidrs() {
+                    if parse_network(cidr).is_none() {
+                        return Err(sources.invalid(
+                            "ALLOWED_SOURCE_CIDRS",
+                            format!("{cidr} is neither a CIDR nor an address"),
+                        ));
+                    }
+                }
+
+                Ok(config)
+            }
+
+            fn cidrs(&self) -> impl Iterator<Item = &str> {
+                self.allowed_source_cidrs
+                    .iter()
+                    .map(|cidr| cidr.trim())
+                    .filter(|cidr| !cidr.is_empty())
+            }
+
+            pub fn allowed_networks(&self) -> Vec<IpNet> {
+                self.cidrs().filter_map(parse_network).collect()
+            }
+        }
+
+        fn parse_network(cidr: &str) -> Option<IpNet> {
+            cidr.parse()
+                .ok()
+                .or_else(|| cidr.parse::<IpAddr>().ok().map(IpNet::from))
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        pub struct RenderingConfig {
+            /// Interval at which a background task fetches and renders the exposition. When set,
+            /// scrapes are served the latest rendered exposition without waiting for anything.
+            pub background_render_interval_millis: Option<u64>,
+            /// Key of the HMAC replacing the `uuid` label of the records on `/metrics`, so that
+            /// the exposition can be published without revealing which player is which. The
+            /// `/api` routes and `render-once` keep presenting the raw UUIDs.
+            pub uuid_pseudonymization_key: Option<String>,
+            /// Present the names of the players on `/metrics`, as a label of a series of their
+            /// own so that renames only churn that series. Names are fetched only when
+            /// `FETCH_PLAYER_NAMES` is set.
+            #[serde(default)]
+            pub player_name_label: bool,
+        }
+
+        impl Settings for RenderingConfig {
+            const SECRETS: &'static [&'static str] = &["uuid_pseudonymization_key"];
+        }
+
+        impl RenderingConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let config: Self = sources.extract()?;
+                if config
+                    .uuid_pseudonymization_key
+                    .as_ref()
+                    .is_some_and(String::is_empty)
+                {
+                    return Err(sources.invalid("UUID_PSEUDONYMIZATION_KEY", "must not be empty"));
+                }
+                if config.player_name_label && config.uuid_pseudonymization_key.is_some() {
+                    return Err(sources.invalid(
+                        "PLAYER_NAME_LABEL",
+                        "would reveal the players behind the pseudonymized UUIDs",
+                    ));
+                }
+
+                Ok(config)
+            }
+
+            pub fn uuid_pseudonymizer(&self) -> Option<super::presenter::UuidPseudonymizer> {
+                self.uuid_pseudonymization_key
+                    .as_deref()
+                    .map(super::presenter::UuidPseudonymizer::new)
+            }
+
+            pub fn background_render_interval(&self) -> Option<Duration> {
+                self.background_render_interval_millis
+                    .map(Duration::from_millis)
+            }
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        pub struct SlowScrapeConfig {
+            /// Scrapes taking longer than this are logged as a warning.
+            pub slow_scrape_threshold_millis: Option<u64>,
+            /// Scrapes responding with a larger body than this, compressed if the client asked
+            /// for it, are logged as a warning.
+            pub large_scrape_threshold_bytes: Option<usize>,
+        }
+
+        impl Settings for SlowScrapeConfig {}
+
+        impl SlowScrapeConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                sources.extract()
+            }
+
+            pub fn slow_scrape_threshold(&self) -> Option<Duration> {
+                self.slow_scrape_threshold_millis.map(Duration::from_millis)
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn snapshot_use_case(state: &SharedAppState) -> GetPlayerDataSnapshotUseCase {
+        let reloadable_config = state.reloadable_config.load();
+
+        GetPlayerDataSnapshotUseCase {
+            get_all_player_data: GetAllPlayerDataUseCase {
+                repository: state.repository.clone(),
+                derived_stats_config: state.derived_stats_config.clone(),
+                fetch_config: reloadable_config.fetch_config.clone(),
+            },
+            previous_snapshot: state.previous_snapshot.clone(),
+            snapshot_cache: state.snapshot_cache.clone(),
+            cache_config: reloadable_config.cache_config.clone(),
+            snapshot_persistence: state.snapshot_persistence.clone(),
+            get_group_totals: state
+                .group_membership_repository
+                .clone()
+                .map(|repository| GetGroupTotalsUseCase { repository }),
+            error_log: state.error_log.clone(),
+            health: state.health.clone(),
+        }
+    }
+
+    /// Spawns a task that periodically refreshes the snapshot and renders its exposition, in
+    /// both the plain and the gzip-compressed form, ahead of the scrapes.
+    #[allow(clippy::must_use_candidate)]
+    pub fn spawn_background_rendering(
+        state: SharedAppState,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                // The watchdog is petted only between iterations, so that systemd restarts the
+                // process if an iteration never finishes
+                crate::infra_systemd::while_petting_watchdog(ticks.tick()).await;
+
+                let result = snapshot_use_case(&state)
+                    .get_snapshot()
+                    .await
+                    .and_then(|snapshot| {
+                        state.rendered_exposition.get_or_render(
+                            &snapshot,
+                            state.repository.connection_state(),
+                            state.record_presentation(),
+                        )
+                    })
+                    .and_then(|rendered| rendered.gzipped().map(drop));
+
+                if let Err(e) = result {
+                    state.error_log.error("background rendering failed", &e);
+                }
+                enforce_cache_memory_budget(&state);
+                crate::infra_systemd::pet_watchdog();
+            }
+        })
+    }
+
+    /// Responds with the exposition, returning the size of the body along with the response.
+    fn exposition_response(
+        rendered: &RenderedExposition,
+        gzip: bool,
+    ) -> anyhow::Result<(Response, usize)> {
+        let headers = [
+            (header::CONTENT_TYPE, EXPOSITION_CONTENT_TYPE),
+            (header::VARY, "accept-encoding"),
+        ];
+        let chunks = if gzip {
+            rendered.gzipped()?
+        } else {
+            rendered.chunks.clone()
+        };
+        let body_size_bytes = chunks.iter().map(Bytes::len).sum();
+
+        let response = if gzip {
+            (
+                StatusCode::OK,
+                headers,
+                [(header::CONTENT_ENCODING, "gzip")],
+                chunked_body(chunks),
+            )
+                .into_response()
+        } else {
+            (StatusCode::OK, headers, chunked_body(chunks)).into_response()
+        };
+
+        Ok((response, body_size_bytes))
+    }
+
+    /// Warns about a scrape that exceeded the configured latency or body size, along with the
+    /// upstream fetch that the served snapshot waited for the longest.
+    fn warn_if_slow_or_large(state: &SharedAppState, elapsed: Duration, body_size_bytes: usize) {
+        let reloadable_config = state.reloadable_config.load();
+        let config = &reloadable_config.slow_scrape_config;
+        let slow = config
+            .slow_scrape_threshold()
+            .is_some_and(|threshold| elapsed > threshold);
+        let large = config
+            .large_scrape_threshold_bytes
+            .is_some_and(|threshold| body_size_bytes > threshold);
+        if !slow && !large {
+            return;
+        }
+
+        let slowest_fetch = state
+            .snapshot_cache
+            .latest()
+            .and_then(|snapshot| snapshot.fetched.slowest_fetch());
+        tracing::warn!(
+            elapsed_millis = elapsed.as_millis(),
+            body_size_bytes,
+            slow,
+            large,
+            slowest_upstream_fetch = slowest_fetch.map(|(kind, _)| kind.as_str()),
+            slowest_upstream_fetch_millis = slowest_fetch.map(|(_, duration)| duration.as_millis()),
+            "scrape exceeded the configured thresholds"
+        );
+    }
+
+    /// Streams the chunks out as they are, without concatenating them.
+    fn chunked_body(
+        chunks: Vec<Bytes>,
+    ) -> StreamBody<impl Stream<Item = Result<Bytes, Infallible>>> {
+        StreamBody::new(stream::iter(chunks.into_iter().map(Ok)))
+    }
+
+    const EXPOSITION_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+    fn accepts_gzip(headers: &HeaderMap) -> bool {
+        headers
+            .get_all(header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|encoding| encoding.split(';').next().map(str::trim) == Some("gzip"))
+    }
+
+    pub mod auth {
+        use axum::http::{header, HeaderValue, Request, StatusCode};
+        use axum::middleware::Next;
+        use axum::response::{IntoResponse, Response};
+        use base64::Engine;
+        use std::sync::{Arc, Mutex, PoisonError};
+        use subtle::ConstantTimeEq;
+        use tokio::sync::Semaphore;
+
+        /// Number of bcrypt verifications run at once. Each takes tens of milliseconds of a
+        /// blocking thread, so requests with wrong passwords wait for a permit rather than
+        /// occupying the whole blocking pool.
+        const MAX_CONCURRENT_VERIFICATIONS: usize = 2;
+
+        /// Compares in constant time, so that secrets cannot be guessed by timing.
+        fn secure_eq(a: &str, b: &str) -> bool {
+            bool::from(a.as_bytes().ct_eq(b.as_bytes()))
+        }
+
+        #[derive(Clone)]
+        struct BasicCredentials {
+            username: String,
+            password_hash: String,
+            /// Credentials that matched the hash last, so that a scraper presenting the same
+            /// ones on every scrape does not cost a bcrypt verification each time.
+            last_verified: Arc<Mutex<Option<String>>>,
+            verifications: Arc<Semaphore>,
+        }
+
+        impl BasicCredentials {
+            async fn verify(&self, encoded: &str) -> bool {
+                let last_verified = self
+                    .last_verified
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone();
+                if last_verified.is_some_and(|last_verified| secure_eq(&last_verified, encoded)) {
+                    return true;
+                }
+
+                let Some((username, password)) = base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .ok()
+                    .and_then(|decoded| String::from_utf8(decoded).ok())
+                    .and_then(|decoded| {
+                        decoded.split_once(':').map(|(username, password)| {
+                            (username.to_string(), password.to_string())
+                        })
+                    })
+                else {
+                    return false;
+                };
+                if !secure_eq(&username, &self.username) {
+                    return false;
+                }
+
+                let Ok(permit) = self.verifications.clone().acquire_owned().await else {
+                    return false;
+                };
+                let password_hash = self.password_hash.clone();
+                let verified = tokio::task::spawn_blocking(move || {
+                    let verified = bcrypt::verify(password, &password_hash).unwrap_or(false);
+                    drop(permit);
+                    verified
+                })
+                .await
+                .unwrap_or(false);
+                if verified {
+                    *self
+                        .last_verified
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner) = Some(encoded.to_string());
+                }
+
+                verified
+            }
+        }
+
+        /// Credentials that requests to the protected routes must present, either of which is
+        /// accepted when both are set.
+        #[derive(Clone, Default)]
+        pub struct RequestAuthenticator {
+            bearer_token: Option<String>,
+            basic_credentials: Option<BasicCredentials>,
+        }
+
+        // The credentials must not end up in logs
+        impl std::fmt::Debug for RequestAuthenticator {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct("RequestAuthenticator")
+                    .field("has_bearer_token", &self.bearer_token.is_some())
+                    .field("has_basic_credentials", &self.basic_credentials.is_some())
+                    .finish()
+            }
+        }
+
+        impl RequestAuthenticator {
+            /// `basic_credentials` are the user name and the bcrypt hash of the password.
+            #[must_use]
+            pub fn new(
+                bearer_token: Option<String>,
+                basic_credentials: Option<(String, String)>,
+            ) -> Self {
+                Self {
+                    bearer_token,
+                    basic_credentials: basic_credentials.map(|(username, password_hash)| {
+                        BasicCredentials {
+                            username,
+                            password_hash,
+                            last_verified: Arc::default(),
+                            verifications: Arc::new(Semaphore::new(MAX_CONCURRENT_VERIFICATIONS)),
+                        }
+                    }),
+                }
+            }
+
+            async fn is_authorized(&self, authorization: Option<&HeaderValue>) -> bool {
+                if self.bearer_token.is_none() && self.basic_credentials.is_none() {
+                    return true;
+                }
+
+                let Some((scheme, credentials)) = authorization
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.split_once(' '))
+                else {
+                    return false;
+                };
+                let credentials = credentials.trim();
+
+                if scheme.eq_ignore_ascii_case("bearer") {
+                    self.bearer_token
+                        .as_ref()
+                        .is_some_and(|token| secure_eq(credentials, token))
+                } else if scheme.eq_ignore_ascii_case("basic") {
+                    match &self.basic_credentials {
+                        Some(basic_credentials) => basic_credentials.verify(credentials).await,
+                        None => false,
+                    }
+                } else {
+                    false
+                }
+            }
+
+            const fn challenge(&self) -> HeaderValue {
+                HeaderValue::from_static(if self.basic_credentials.is_some() {
+                    "Basic realm=\"seichi-timed-stats-translator\""
+                } else {
+                    "Bearer"
+                })
+            }
+
+            /// Middleware rejecting the requests that do not present the credentials.
+            pub async fn check<B: Send>(self, request: Request<B>, next: Next<B>) -> Response {
+                let authorization = request.headers().get(header::AUTHORIZATION).cloned();
+
+                if !self.is_authorized(authorization.as_ref()).await {
+                    return (
+                        StatusCode::UNAUTHORIZED,
+                        [(header::WWW_AUTHENTICATE, self.challenge())],
+                        "Missing or invalid credentials.",
+                    )
+                        .into_response();
+                }
+
+                next.run(request).await
+            }
+        }
+    }
+
+    pub mod listener {
+        use anyhow::{anyhow, Context as _};
+        use axum::extract::connect_info::Connected;
+        use futures::stream::{self, Stream, StreamExt};
+        use std::io::{self, BufReader};
+        use std::net::SocketAddr;
+        use std::pin::Pin;
+        use std::sync::Arc;
+        use std::task::{Context, Poll};
+        use std::time::Duration;
+        use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+        use tokio::net::{TcpListener, TcpStream};
+        use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+        use tokio_rustls::rustls::server::AllowAnyAuthenticatedClient;
+        use tokio_rustls::rustls::{self, Certificate, PrivateKey, RootCertStore};
+        use tokio_rustls::server::TlsStream;
+        use tokio_rustls::TlsAcceptor;
+
+        /// Largest number of TLS handshakes performed at once.
+        const MAX_CONCURRENT_HANDSHAKES: usize = 64;
+
+        /// Time after which a client that has not completed the TLS handshake is disconnected.
+        const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+        /// Time waited before accepting again after a failure, which is usually the process
+        /// running out of file descriptors.
+        const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+        fn read_certificates(path: &str) -> anyhow::Result<Vec<Certificate>> {
+            let file =
+                std::fs::File::open(path).with_context(|| format!("Failed to read {path}"))?;
+
+            Ok(rustls_pemfile::certs(&mut BufReader::new(file))?
+                .into_iter()
+                .map(Certificate)
+                .collect())
+        }
+
+        fn read_private_key(path: &str) -> anyhow::Result<PrivateKey> {
+            let file =
+                std::fs::File::open(path).with_context(|| format!("Failed to read {path}"))?;
+
+            rustls_pemfile::read_all(&mut BufReader::new(file))?
+                .into_iter()
+                .find_map(|item| match item {
+                    rustls_pemfile::Item::PKCS8Key(key)
+                    | rustls_pemfile::Item::RSAKey(key)
+                    | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+                    _ => None,
+                })
+                .ok_or_else(|| anyhow!("{path} contains no private key"))
+        }
+
+        /// Builds the TLS configuration of the server, which requires clients to present a
+        /// certificate issued by `client_ca_cert_path` if it is set.
+        #[allow(clippy::missing_errors_doc)]
+        pub fn tls_acceptor(
+            cert_path: &str,
+            key_path: &str,
+            client_ca_cert_path: Option<&str>,
+        ) -> anyhow::Result<TlsAcceptor> {
+            let builder = rustls::ServerConfig::builder().with_safe_defaults();
+            let builder = match client_ca_cert_path {
+                Some(path) => {
+                    let mut roots = RootCertStore::empty();
+                    for certificate in read_certificates(path)? {
+                        roots.add(&certificate)?;
+                    }
+                    builder.with_client_cert_verifier(Arc::new(AllowAnyAuthenticatedClient::new(
+                        roots,
+                    )))
+                }
+                None => builder.with_no_client_auth(),
+            };
+            let mut config = builder
+                .with_single_cert(read_certificates(cert_path)?, read_private_key(key_path)?)?;
+            config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+            Ok(TlsAcceptor::from(Arc::new(config)))
+        }
+
+        #[derive(Debug)]
+        enum Transport {
+            Plain(TcpStream),
+            Tls(Box<TlsStream<TcpStream>>),
+        }
+
+        /// An accepted connection, holding one of the permits of the listener while it is open.
+        #[derive(Debug)]
+        pub struct Connection {
+            stream: Transport,
+            remote_address: SocketAddr,
+            _permit: OwnedSemaphorePermit,
+        }
+
+        impl Connected<&Connection> for SocketAddr {
+            fn connect_info(connection: &Connection) -> Self {
+                connection.remote_address
+            }
+        }
+
+        impl AsyncRead for Connection {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut ReadBuf<'_>,
+            ) -> Poll<io::Result<()>> {
+                match &mut self.stream {
+                    Transport::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+                    Transport::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+                }
+            }
+        }
+
+        impl AsyncWrite for Connection {
+            fn poll_write(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                match &mut self.stream {
+                    Transport::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+                    Transport::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+                }
+            }
+
+            fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                match &mut self.stream {
+                    Transport::Plain(stream) => Pin::new(stream).poll_flush(cx),
+                    Transport::Tls(stream) => Pin::new(stream).poll_flush(cx),
+                }
+            }
+
+            fn poll_shutdown(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<io::Result<()>> {
+                match &mut self.stream {
+                    Transport::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+                    Transport::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+                }
+            }
+        }
+
+        /// Listens on `address`, serving TLS if `tls` is set.
+        ///
+        /// A connection is accepted only while fewer than `max_connections` are open.
+        /// Connections that fail to be accepted or to complete the TLS handshake are skipped,
+        /// so that the stream never ends with an error and takes the server down with it.
+        #[allow(clippy::missing_errors_doc)]
+        pub async fn bind(
+            address: SocketAddr,
+            max_connections: Option<usize>,
+            tls: Option<TlsAcceptor>,
+        ) -> io::Result<impl Stream<Item = Connection>> {
+            let listener = TcpListener::bind(address).await?;
+            let permits = Arc::new(Semaphore::new(
+                max_connections.unwrap_or(Semaphore::MAX_PERMITS),
+            ));
+
+            let accepted = stream::unfold((listener, permits), |(listener, permits)| async move {
+                let Ok(permit) = permits.clone().acquire_owned().await else {
+                    return None;
+                };
+
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, remote_address)) => {
+                            return Some(((stream, remote_address, permit), (listener, permits)))
+                        }
+                        Err(e) => {
+                            tracing::warn!("failed to accept a connection: {:?}", e);
+                            tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                        }
+                    }
+                }
+            });
+
+            Ok(accepted
+                .map(move |(stream, remote_address, permit)| {
+                    let tls = tls.clone();
+
+                    async move {
+                        let stream = match tls {
+                            Some(tls) => {
+                                match tokio::time::timeout(HANDSHAKE_TIMEOUT, tls.accept(stream))
+                                    .await
+                                {
+                                    Ok(Ok(stream)) => Transport::Tls(Box::new(stream)),
+                                    Ok(Err(e)) => {
+                                        tracing::debug!("TLS handshake failed: {:?}", e);
+                                        return None;
+                                    }
+                                    Err(_) => {
+                                        tracing::debug!("TLS handshake timed out");
+                                        return None;
+                                    }
+                                }
+                            }
+                            None => Transport::Plain(stream),
+                        };
+
+                        Some(Connection {
+                            stream,
+                            remote_address,
+                            _permit: permit,
+                        })
+                    }
+                })
+                .buffer_unordered(MAX_CONCURRENT_HANDSHAKES)
+                .filter_map(std::future::ready))
+        }
+    }
+
+    /// Networks that requests are accepted from.
+    #[derive(Debug, Clone)]
+    pub struct SourceAllowlist {
+        networks: Arc<[IpNet]>,
+        trusted_proxy_depth: usize,
+    }
+
+    impl SourceAllowlist {
+        #[must_use]
+        pub fn new(networks: Vec<IpNet>, trusted_proxy_depth: usize) -> Self {
+            Self {
+                networks: networks.into(),
+                trusted_proxy_depth,
+            }
+        }
+
+        /// Address of the client, appended to `X-Forwarded-For` by the outermost trusted proxy.
+        /// Entries further left were written by the client or by untrusted proxies, and could
+        /// claim any address.
+        fn client_address<B>(&self, request: &Request<B>) -> Option<IpAddr> {
+            if self.trusted_proxy_depth == 0 {
+                return request
+                    .extensions()
+                    .get::<ConnectInfo<SocketAddr>>()
+                    .map(|ConnectInfo(address)| address.ip());
+            }
+
+            let forwarded = request
+                .headers()
+                .get_all("x-forwarded-for")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .collect::<Vec<_>>();
+
+            forwarded
+                .len()
+                .checked_sub(self.trusted_proxy_depth)
+                .and_then(|index| forwarded[index].parse().ok())
+        }
+
+        /// Middleware rejecting the requests from outside of the networks, unless none are set.
+        pub async fn check<B>(self, request: Request<B>, next: Next<B>) -> Response {
+            let allowed = self.networks.is_empty()
+                || self
+                    .client_address(&request)
+                    .map(|address| address.to_canonical())
+                    .is_some_and(|address| {
+                        self.networks
+                            .iter()
+                            .any(|network| network.contains(&address))
+                    });
+
+            if !allowed {
+                return (
+                    StatusCode::FORBIDDEN,
+                    "Requests are not accepted from this address.",
+                )
+                    .into_response();
+            }
+
+            next.run(request).await
+        }
+    }
+
+    /// Middleware rejecting the requests whose body is larger than `max_bytes`.
+    ///
+    /// The headers are only trusted to reject a request early: the body is read up to the limit
+    /// either way, so that a chunked body or one longer than its `Content-Length` cannot slip
+    /// through. A request declaring both a `Content-Length` and a `Transfer-Encoding` is
+    /// rejected, as the two may be read differently by a proxy in front of the server.
+    pub async fn limit_request_body(
+        max_bytes: u64,
+        request: Request<body::Body>,
+        next: Next<body::Body>,
+    ) -> Response {
+        let too_large = || {
+            (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Request bodies are limited to {max_bytes} bytes."),
+            )
+                .into_response()
+        };
+
+        let content_length = request.headers().get(header::CONTENT_LENGTH);
+        let transfer_encoded = request.headers().contains_key(header::TRANSFER_ENCODING);
+        if content_length.is_some() && transfer_encoded {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Requests may not declare both a Content-Length and a Transfer-Encoding.",
+            )
+                .into_response();
+        }
+        let declared_too_large = content_length
+            .and_then(|value| value.to_str().ok()?.parse::<u64>().ok())
+            .map_or(transfer_encoded && max_bytes == 0, |content_length| {
+                content_length > max_bytes
+            });
+        if declared_too_large {
+            return too_large();
+        }
+
+        let (parts, mut request_body) = request.into_parts();
+        let mut read = Vec::new();
+        while let Some(chunk) = hyper::body::HttpBody::data(&mut request_body).await {
+            let Ok(chunk) = chunk else {
+                return (StatusCode::BAD_REQUEST, "Failed to read the request body.")
+                    .into_response();
+            };
+            if (read.len() + chunk.len()) as u64 > max_bytes {
+                return too_large();
+            }
+            read.extend_from_slice(&chunk);
+        }
+
+        next.run(Request::from_parts(parts, body::Body::from(read)))
+            .await
+    }
+
+    pub mod presenter {
+        use crate::domain::{
+            AggregatedPlayerData, EfficiencyStats, FetchedPlayerData, GroupTotals, Player,
+            PlayerDataKind, PlayerStats, PlayerUuid, PopulationSummary, UpstreamConnectionState,
+        };
+        use crate::use_cases::PlayerDataSnapshot;
+        use axum::body::Bytes;
+        use ring::hmac;
+        use std::fmt::Write;
+        use std::time::{Duration, SystemTime};
+
+        /// Length of a pseudonym in hex digits. Half of the HMAC is plenty to keep pseudonyms of
+        /// different players apart.
+        const PSEUDONYM_LENGTH: usize = 32;
+
+        /// Replaces player UUIDs with a keyed hash of them, which stays the same for a player as
+        /// long as the key does, but cannot be traced back to the player without the key.
+        pub struct UuidPseudonymizer {
+            key: hmac::Key,
+        }
+
+        impl UuidPseudonymizer {
+            #[must_use]
+            pub fn new(key: &str) -> Self {
+                Self {
+                    key: hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes()),
+                }
+            }
+
+            fn encode<'b>(
+                &self,
+                uuid: &PlayerUuid,
+                buffer: &'b mut [u8; PSEUDONYM_LENGTH],
+            ) -> &'b str {
+                let tag = hmac::sign(&self.key, uuid.as_bytes());
+                hex::encode_to_slice(&tag.as_ref()[..PSEUDONYM_LENGTH / 2], buffer)
+                    .expect("the buffer fits the encoded bytes");
+                std::str::from_utf8(buffer).expect("hex digits are valid UTF-8")
+            }
+        }
+
+        impl std::fmt::Debug for UuidPseudonymizer {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct("UuidPseudonymizer").finish_non_exhaustive()
+            }
+        }
+
+        /// `player_data` records of a player as of the last render, with the values they show.
+        #[derive(Debug)]
+        struct PlayerRecordFragment {
+            player: Player,
+            values: [u64; PlayerDataKind::ALL.len()],
+            text: Box<str>,
+        }
+
+        /// Records of each player rendered by the last render, in the order they were presented,
+        /// to be reused by the next render for the players whose values have not changed since.
+        ///
+        /// Players keep their order between refreshes as long as the upstream returns them in the
+        /// same order, so fragments are matched by position rather than looked up by player,
+        /// which would cost about as much as formatting the records again.
+        #[derive(Debug, Default)]
+        pub struct PlayerRecordFragments {
+            presented_kinds: Vec<PlayerDataKind>,
+            pseudonymized: bool,
+            season: Option<String>,
+            labels: RecordLabels,
+            fragments: Vec<PlayerRecordFragment>,
+        }
+
+        impl PlayerRecordFragments {
+            pub fn clear(&mut self) {
+                self.fragments = Vec::new();
+            }
+
+            #[must_use]
+            pub fn approximate_size_bytes(&self) -> usize {
+                self.fragments.capacity() * std::mem::size_of::<PlayerRecordFragment>()
+                    + self
+                        .fragments
+                        .iter()
+                        .map(|fragment| fragment.text.len())
+                        .sum::<usize>()
+            }
+
+            /// Starts a render presenting `presented_kinds` of `season`. Fragments presenting other
+            /// kinds, or labelled another way, can never be reused, so they are dropped.
+            fn begin_render(
+                &mut self,
+                presented_kinds: &[PlayerDataKind],
+                pseudonymized: bool,
+                season: Option<&str>,
+            ) {
+                self.labels = RecordLabels::new(season);
+                if self.presented_kinds != presented_kinds
+                    || self.pseudonymized != pseudonymized
+                    || self.season.as_deref() != season
+                {
+                    self.presented_kinds = presented_kinds.to_vec();
+                    self.pseudonymized = pseudonymized;
+                    self.season = season.map(str::to_string);
+                    self.clear();
+                }
+            }
+
+            /// Records of `player`, presented at `position`, formatted again unless the same
+            /// player was presented there with the same values by the last render.
+            fn records_of(
+                &mut self,
+                position: usize,
+                player: &Player,
+                data: &AggregatedPlayerData,
+                uuid_pseudonymizer: Option<&UuidPseudonymizer>,
+            ) -> &str {
+                let values = PlayerDataKind::ALL.map(|kind| data.get(kind));
+                let reusable = self.fragments.get(position).is_some_and(|fragment| {
+                    fragment.player == *player && fragment.values == values
+                });
+
+                if !reusable {
+                    let mut text = String::new();
+                    for kind in &self.presented_kinds {
+                        write_record(
+                            &mut text,
+                            player,
+                            uuid_pseudonymizer,
+                            self.labels.suffix(*kind),
+                            data.get(*kind),
+                        );
+                    }
+                    let fragment = PlayerRecordFragment {
+                        player: *player,
+                        values,
+                        text: text.into_boxed_str(),
+                    };
+
+                    if position < self.fragments.len() {
+                        self.fragments[position] = fragment;
+                    } else {
+                        self.fragments.push(fragment);
+                    }
+                }
+
+                &self.fragments[position].text
+            }
+
+            /// Drops the fragments past the players that the render presented.
+            fn end_render(&mut self, player_count: usize) {
+                self.fragments.truncate(player_count);
+            }
+        }
+
+        #[must_use]
+        pub fn present_player_stats_as_json(
+            uuid: &PlayerUuid,
+            stats: &PlayerStats,
+        ) -> serde_json::Value {
+            let mut result = serde_json::Map::new();
+            result.insert("uuid".to_string(), uuid.to_string().into());
+            if let Some(name) = &stats.name {
+                result.insert("name".to_string(), name.clone().into());
+            }
+
+            for (kind, count) in &stats.counts {
+                result.insert(kind.as_str().to_string(), (*count).into());
+            }
+            if let Some(play_hours) = stats.play_hours {
+                result.insert("play_hours".to_string(), play_hours.into());
+            }
+            for (name, ratio) in EfficiencyStats::NAMES
+                .into_iter()
+                .zip(stats.efficiency.ratios())
+            {
+                if let Some(ratio) = ratio {
+                    result.insert(name.to_string(), ratio.into());
+                }
+            }
+            if !stats.ranks.is_empty() {
+                let ranks = stats
+                    .ranks
+                    .iter()
+                    .map(|(kind, rank)| (kind.as_str().to_string(), (*rank).into()))
+                    .collect::<serde_json::Map<_, _>>();
+                result.insert("ranks".to_string(), ranks.into());
+            }
+            if !stats.percentiles.is_empty() {
+                let percentiles = stats
+                    .percentiles
+                    .iter()
+                    .map(|(kind, percentile)| (kind.as_str().to_string(), (*percentile).into()))
+                    .collect::<serde_json::Map<_, _>>();
+                result.insert("percentiles".to_string(), percentiles.into());
+            }
+            if let Some(last_seen) = stats.last_seen {
+                result.insert(
+                    "last_seen".to_string(),
+                    humantime::format_rfc3339_seconds(last_seen)
+                        .to_string()
+                        .into(),
+                );
+            }
+            if let Some(season) = &stats.season {
+                result.insert("season".to_string(), season.clone().into());
+            }
+
+            serde_json::Value::Object(result)
+        }
+
+        /// Totals of each group as a JSON object per group, holding the kinds that were fetched.
+        #[must_use]
+        pub fn present_group_totals_as_json(
+            fetched: &FetchedPlayerData,
+            group_totals: &GroupTotals,
+        ) -> serde_json::Value {
+            let groups = group_totals
+                .0
+                .iter()
+                .map(|(group, totals)| {
+                    let mut result = serde_json::Map::new();
+                    result.insert("group".to_string(), group.clone().into());
+                    result.insert("member_count".to_string(), totals.member_count.into());
+                    for kind in PlayerDataKind::ALL
+                        .into_iter()
+                        .filter(|kind| fetched.has_kind(*kind))
+                    {
+                        result.insert(kind.as_str().to_string(), totals.get(kind).into());
+                    }
+
+                    serde_json::Value::Object(result)
+                })
+                .collect::<Vec<_>>();
+
+            serde_json::json!({ "groups": groups })
+        }
+
+        /// Summary of each fetched kind as a JSON object per kind.
+        #[must_use]
+        pub fn present_population_summary_as_json(
+            summary: &PopulationSummary,
+        ) -> serde_json::Value {
+            let kinds = summary
+                .kinds
+                .iter()
+                .map(|(kind, summary)| {
+                    (
+                        kind.as_str().to_string(),
+                        serde_json::json!({
+                            "count": summary.count,
+                            "sum": summary.sum,
+                            "mean": summary.mean,
+                            "median": summary.median,
+                            "max": summary.max,
+                        }),
+                    )
+                })
+                .collect::<serde_json::Map<_, _>>();
+
+            serde_json::json!({
+                "player_count": summary.player_count,
+                "kinds": kinds,
+            })
+        }
+
+        fn write_population_summary(
+            target: &mut String,
+            summary: &PopulationSummary,
+        ) -> anyhow::Result<()> {
+            target.write_str("# HELP seichi_population_players Number of players the fetched data holds statistics of\n")?;
+            target.write_str("# TYPE seichi_population_players gauge\n")?;
+            writeln!(target, "seichi_population_players {}", summary.player_count)?;
+
+            target.write_str("# HELP seichi_population_data Player metrics summarized over the players, partitioned by kind and statistic\n")?;
+            target.write_str("# TYPE seichi_population_data gauge\n")?;
+            for (kind, summary) in &summary.kinds {
+                let kind = kind.as_str();
+                writeln!(
+                    target,
+                    r#"seichi_population_data{{kind="{kind}",statistic="count"}} {}"#,
+                    summary.count
+                )?;
+                writeln!(
+                    target,
+                    r#"seichi_population_data{{kind="{kind}",statistic="sum"}} {}"#,
+                    summary.sum
+                )?;
+                writeln!(
+                    target,
+                    r#"seichi_population_data{{kind="{kind}",statistic="mean"}} {}"#,
+                    summary.mean
+                )?;
+                writeln!(
+                    target,
+                    r#"seichi_population_data{{kind="{kind}",statistic="median"}} {}"#,
+                    summary.median
+                )?;
+                writeln!(
+                    target,
+                    r#"seichi_population_data{{kind="{kind}",statistic="max"}} {}"#,
+                    summary.max
+                )?;
+            }
+
+            Ok(())
+        }
+
+        fn write_group_totals(
+            target: &mut String,
+            fetched: &FetchedPlayerData,
+            group_totals: &GroupTotals,
+        ) -> anyhow::Result<()> {
+            target.write_str("# HELP seichi_group_members Number of members of each group of players that have statistics\n")?;
+            target.write_str("# TYPE seichi_group_members gauge\n")?;
+            for (group, totals) in &group_totals.0 {
+                writeln!(
+                    target,
+                    r#"seichi_group_members{{group="{}"}} {}"#,
+                    escape_label_value(group),
+                    totals.member_count
+                )?;
+            }
+
+            target.write_str("# HELP seichi_group_data Player metrics totalled over the members of each group, partitioned by group and kind\n")?;
+            target.write_str("# TYPE seichi_group_data gauge\n")?;
+            for (group, totals) in &group_totals.0 {
+                let group = escape_label_value(group);
+                for kind in PlayerDataKind::ALL
+                    .into_iter()
+                    .filter(|kind| fetched.has_kind(*kind))
+                {
+                    writeln!(
+                        target,
+                        r#"seichi_group_data{{group="{group}",kind="{}"}} {}"#,
+                        kind.as_str(),
+                        totals.get(kind)
+                    )?;
+                }
+            }
+
+            Ok(())
+        }
+
+        fn write_fetch_errors(
+            target: &mut String,
+            fetched: &FetchedPlayerData,
+        ) -> anyhow::Result<()> {
+            target.write_str("# HELP seichi_fetch_errors Whether the last fetch of each kind of player data failed\n")?;
+            target.write_str("# TYPE seichi_fetch_errors gauge\n")?;
+
+            for kind in PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| fetched.is_enabled(*kind))
+            {
+                writeln!(
+                    target,
+                    r#"seichi_fetch_errors{{kind="{}"}} {}"#,
+                    kind.as_str(),
+                    u8::from(!fetched.has_kind(kind))
+                )?;
+            }
+
+            Ok(())
+        }
+
+        /// Help text of the series of each ratio, in the order of [`EfficiencyStats::NAMES`].
+        const EFFICIENCY_HELPS: [&str; 3] = [
+            "Blocks broken per hour of play time, derived from break_count and play_ticks",
+            "Blocks placed per hour of play time, derived from build_count and play_ticks",
+            "Votes per 30 days since votes started being counted, derived from vote_count",
+        ];
+
+        /// Periods over which recently active players are counted, with their labels.
+        const RECENT_ACTIVITY_WINDOWS: [(&str, Duration); 4] = [
+            ("1h", Duration::from_hours(1)),
+            ("1d", Duration::from_hours(24)),
+            ("7d", Duration::from_hours(7 * 24)),
+            ("30d", Duration::from_hours(30 * 24)),
+        ];
+
+        fn write_recently_active_players(
+            target: &mut String,
+            fetched: &FetchedPlayerData,
+        ) -> anyhow::Result<()> {
+            if !fetched.data.0.values().any(|data| data.last_seen.is_some()) {
+                return Ok(());
+            }
+
+            target.write_str("# HELP seichi_recently_active_players Number of players last seen within each window before the render\n")?;
+            target.write_str("# TYPE seichi_recently_active_players gauge\n")?;
+
+            let now = SystemTime::now();
+            for (label, window) in RECENT_ACTIVITY_WINDOWS {
+                let since = now.checked_sub(window).unwrap_or(SystemTime::UNIX_EPOCH);
+                writeln!(
+                    target,
+                    r#"seichi_recently_active_players{{window="{label}"}} {}"#,
+                    fetched.data.count_seen_since(since)
+                )?;
+            }
+
+            Ok(())
+        }
+
+        fn write_implausible_values(
+            target: &mut String,
+            fetched: &FetchedPlayerData,
+        ) -> anyhow::Result<()> {
+            target.write_str("# HELP seichi_implausible_values Number of players whose value of each kind exceeds its plausibility bound and is left out of the rankings\n")?;
+            target.write_str("# TYPE seichi_implausible_values gauge\n")?;
+
+            for kind in PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| fetched.has_kind(*kind))
+            {
+                writeln!(
+                    target,
+                    r#"seichi_implausible_values{{kind="{}"}} {}"#,
+                    kind.as_str(),
+                    fetched.data.count_implausible(kind)
+                )?;
+            }
+
+            Ok(())
+        }
+
+        fn write_duplicate_records(
+            target: &mut String,
+            fetched: &FetchedPlayerData,
+        ) -> anyhow::Result<()> {
+            target.write_str("# HELP seichi_duplicate_records Number of records of each kind in the last fetch that duplicated a player already received\n")?;
+            target.write_str("# TYPE seichi_duplicate_records gauge\n")?;
+
+            for kind in PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| fetched.has_kind(*kind))
+            {
+                writeln!(
+                    target,
+                    r#"seichi_duplicate_records{{kind="{}"}} {}"#,
+                    kind.as_str(),
+                    fetched.duplicate_records.get(&kind).copied().unwrap_or(0)
+                )?;
+            }
+
+            Ok(())
+        }
+
+        /// Size from which the exposition being written is cut into a new chunk.
+        const CHUNK_SIZE: usize = 64 * 1024;
+
+        /// Room left in each chunk for the records of the player that crosses `CHUNK_SIZE`.
+        const CHUNK_SLACK: usize = 1024;
+
+        /// Moves `current` to the finished chunks once it has grown past `CHUNK_SIZE`.
+        fn cut_chunk_if_full(chunks: &mut Vec<Bytes>, current: &mut String) {
+            if current.len() >= CHUNK_SIZE {
+                let chunk =
+                    std::mem::replace(current, String::with_capacity(CHUNK_SIZE + CHUNK_SLACK));
+                chunks.push(Bytes::from(chunk));
+            }
+        }
+
+        fn write_build_info(target: &mut String) -> anyhow::Result<()> {
+            target.write_str(
+                "# HELP seichi_translator_info Build information of the running translator\n",
+            )?;
+            target.write_str("# TYPE seichi_translator_info gauge\n")?;
+            writeln!(
+                target,
+                r#"seichi_translator_info{{version="{}",commit="{}",proto_rev="{}"}} 1"#,
+                env!("CARGO_PKG_VERSION"),
+                env!("BUILD_GIT_COMMIT"),
+                env!("BUILD_SEICHI_GAME_DATA_PROTO_REF"),
+            )?;
+
+            Ok(())
+        }
+
+        fn write_upstream_connection_state(
+            target: &mut String,
+            connection_state: UpstreamConnectionState,
+        ) -> anyhow::Result<()> {
+            target.write_str("# HELP seichi_translator_upstream_connection_state Connection state of the game data server client\n")?;
+            target.write_str("# TYPE seichi_translator_upstream_connection_state gauge\n")?;
+
+            for state in UpstreamConnectionState::ALL {
+                writeln!(
+                    target,
+                    r#"seichi_translator_upstream_connection_state{{state="{}"}} {}"#,
+                    state.as_str(),
+                    u8::from(state == connection_state)
+                )?;
+            }
+
+            Ok(())
+        }
+
+        /// Escapes a label value as required by the text exposition format.
+        fn escape_label_value(value: &str) -> String {
+            value
+                .replace('\\', r"\\")
+                .replace('"', r#"\""#)
+                .replace('\n', r"\n")
+        }
+
+        /// Labels following the uuid of the player series, which are the same for every player
+        /// in a render.
+        #[derive(Debug, Default)]
+        struct RecordLabels {
+            /// The `season` label with its leading comma, or nothing if the season is unknown.
+            season: String,
+            /// Part of the player records of each kind following the uuid, up to the value,
+            /// indexed by [`PlayerDataKind::index`].
+            suffixes: [String; PlayerDataKind::ALL.len()],
+        }
+
+        impl RecordLabels {
+            fn new(season: Option<&str>) -> Self {
+                let season = season.map_or_else(String::new, |season| {
+                    format!(r#",season="{}""#, escape_label_value(season))
+                });
+                let suffixes = PlayerDataKind::ALL
+                    .map(|kind| format!(r#"",kind="{}"{season}}} "#, kind.as_str()));
+
+                Self { season, suffixes }
+            }
+
+            fn suffix(&self, kind: PlayerDataKind) -> &str {
+                &self.suffixes[kind.index()]
+            }
+        }
+
+        // Records are written piece by piece rather than with `format!`, as they make up almost
+        // all of the exposition and formatting them dominates the time spent on a scrape.
+        fn write_uuid(
+            target: &mut String,
+            player: &Player,
+            uuid_pseudonymizer: Option<&UuidPseudonymizer>,
+        ) {
+            if let Some(pseudonymizer) = uuid_pseudonymizer {
+                let mut pseudonym_buffer = [0; PSEUDONYM_LENGTH];
+                target.push_str(pseudonymizer.encode(&player.uuid, &mut pseudonym_buffer));
+            } else {
+                let mut uuid_buffer = [0; uuid::fmt::Hyphenated::LENGTH];
+                target.push_str(player.uuid.encode_hyphenated(&mut uuid_buffer));
+            }
+        }
+
+        /// Writes the uuid label value, followed by the `server` label if the records of the
+        /// player come from a single game server.
+        fn write_player(
+            target: &mut String,
+            player: &Player,
+            uuid_pseudonymizer: Option<&UuidPseudonymizer>,
+        ) {
+            write_uuid(target, player, uuid_pseudonymizer);
+            if let Some(server) = player.server {
+                target.push_str(r#"",server=""#);
+                target.push_str(server.as_str());
+            }
+        }
+
+        fn write_player_labels(
+            target: &mut String,
+            metric_name_and_uuid_label: &str,
+            player: &Player,
+            uuid_pseudonymizer: Option<&UuidPseudonymizer>,
+            labels_suffix: &str,
+        ) {
+            target.push_str(metric_name_and_uuid_label);
+            write_player(target, player, uuid_pseudonymizer);
+            target.push_str(labels_suffix);
+        }
+
+        fn write_delta_record(
+            target: &mut String,
+            player: &Player,
+            uuid_pseudonymizer: Option<&UuidPseudonymizer>,
+            labels_suffix: &str,
+            value: i64,
+        ) {
+            write_player_labels(
+                target,
+                r#"player_data_delta{uuid=""#,
+                player,
+                uuid_pseudonymizer,
+                labels_suffix,
+            );
+            target.push_str(itoa::Buffer::new().format(value));
+            target.push('\n');
+        }
+
+        fn write_record(
+            target: &mut String,
+            player: &Player,
+            uuid_pseudonymizer: Option<&UuidPseudonymizer>,
+            labels_suffix: &str,
+            value: u64,
+        ) {
+            write_player_labels(
+                target,
+                r#"player_data{uuid=""#,
+                player,
+                uuid_pseudonymizer,
+                labels_suffix,
+            );
+            target.push_str(itoa::Buffer::new().format(value));
+            target.push('\n');
+        }
+
+        /// How the records of the players are presented.
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct RecordPresentation<'a> {
+            /// Replaces the UUIDs of the players, if set.
+            pub uuid_pseudonymizer: Option<&'a UuidPseudonymizer>,
+            /// Presents the ranks of the players ranked within this many places, if set.
+            pub rank_series_top_n: Option<u32>,
+            /// Presents the names of the players as labels of `seichi_player_info`.
+            pub player_names: bool,
+        }
+
+        /// Renders the exposition as chunks of about `CHUNK_SIZE` bytes, each ending at the end
+        /// of a line, so that it is never held in one contiguous allocation.
+        #[tracing::instrument(skip(snapshot), fields(snapshot_id = snapshot.id))]
+        pub fn present_player_data_as_prometheus_metrics(
+            snapshot: &PlayerDataSnapshot,
+            connection_state: UpstreamConnectionState,
+            record_presentation: RecordPresentation<'_>,
+            record_fragments: &mut PlayerRecordFragments,
+        ) -> anyhow::Result<Vec<Bytes>> {
+            let fetched = &*snapshot.fetched;
+            let deltas = snapshot.deltas.as_ref();
+            let restored = snapshot.restored;
+            let RecordPresentation {
+                uuid_pseudonymizer,
+                rank_series_top_n,
+                player_names,
+            } = record_presentation;
+            let data = &fetched.data;
+            let mut chunks = Vec::new();
+            let mut result = String::with_capacity(CHUNK_SIZE + CHUNK_SLACK);
+
+            write_build_info(&mut result)?;
+            write_upstream_connection_state(&mut result, connection_state)?;
+            result.write_str("# HELP seichi_translator_snapshot_restored Whether the data was restored from a previous run and has not been refreshed yet\n")?;
+            result.write_str("# TYPE seichi_translator_snapshot_restored gauge\n")?;
+            writeln!(
+                result,
+                "seichi_translator_snapshot_restored {}",
+                u8::from(restored)
+            )?;
+            write_fetch_errors(&mut result, fetched)?;
+            write_duplicate_records(&mut result, fetched)?;
+            write_implausible_values(&mut result, fetched)?;
+            write_recently_active_players(&mut result, fetched)?;
+            write_population_summary(&mut result, &snapshot.summary)?;
+            if let Some(group_totals) = &snapshot.group_totals {
+                write_group_totals(&mut result, fetched, group_totals)?;
+            }
+
+            result
+                .write_str("# HELP player_data Player metrics, partitioned by uuid and kind\n")?;
+            result.write_str("# TYPE player_data gauge\n")?;
+
+            let presented_kinds = PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| fetched.has_kind(*kind))
+                .collect::<Vec<_>>();
+
+            record_fragments.begin_render(
+                &presented_kinds,
+                uuid_pseudonymizer.is_some(),
+                fetched.season.as_deref(),
+            );
+            for (position, (player, data)) in data.0.iter().enumerate() {
+                result.push_str(record_fragments.records_of(
+                    position,
+                    player,
+                    data,
+                    uuid_pseudonymizer,
+                ));
+                cut_chunk_if_full(&mut chunks, &mut result);
+            }
+            record_fragments.end_render(data.0.len());
+
+            if data.0.values().any(|d| d.play_hours.is_some()) {
+                result.write_str(
+                    "# HELP seichi_player_play_hours Player play time in hours, derived from play_ticks\n",
+                )?;
+                result.write_str("# TYPE seichi_player_play_hours gauge\n")?;
+
+                for (player, data) in &data.0 {
+                    if let Some(play_hours) = data.play_hours {
+                        result.push_str(r#"seichi_player_play_hours{uuid=""#);
+                        write_player(&mut result, player, uuid_pseudonymizer);
+                        writeln!(
+                            result,
+                            r#""{}}} {play_hours}"#,
+                            record_fragments.labels.season
+                        )?;
+                        cut_chunk_if_full(&mut chunks, &mut result);
+                    }
+                }
+            }
+
+            for ((index, name), help) in EfficiencyStats::NAMES
+                .into_iter()
+                .enumerate()
+                .zip(EFFICIENCY_HELPS)
+            {
+                if !data
+                    .0
+                    .values()
+                    .any(|d| d.efficiency.ratios()[index].is_some())
+                {
+                    continue;
+                }
+
+                writeln!(result, "# HELP seichi_player_{name} {help}")?;
+                writeln!(result, "# TYPE seichi_player_{name} gauge")?;
+
+                for (player, data) in &data.0 {
+                    if let Some(ratio) = data.efficiency.ratios()[index] {
+                        write!(result, r#"seichi_player_{name}{{uuid=""#)?;
+                        write_player(&mut result, player, uuid_pseudonymizer);
+                        writeln!(result, r#""{}}} {ratio}"#, record_fragments.labels.season)?;
+                        cut_chunk_if_full(&mut chunks, &mut result);
+                    }
+                }
+            }
+
+            if player_names && data.0.values().any(|d| d.name.is_some()) {
+                result.write_str("# HELP seichi_player_info Name each player was last known by, to be joined with the other series by uuid\n")?;
+                result.write_str("# TYPE seichi_player_info gauge\n")?;
+
+                for (player, data) in &data.0 {
+                    if let Some(name) = &data.name {
+                        result.push_str(r#"seichi_player_info{uuid=""#);
+                        write_player(&mut result, player, uuid_pseudonymizer);
+                        writeln!(
+                            result,
+                            r#"",name="{}"{}}} 1"#,
+                            escape_label_value(name),
+                            record_fragments.labels.season
+                        )?;
+                        cut_chunk_if_full(&mut chunks, &mut result);
+                    }
+                }
+            }
+
+            if let Some(top_n) = rank_series_top_n {
+                result.write_str("# HELP seichi_player_rank Rank of the players ranked within the top places by each kind, the largest value first\n")?;
+                result.write_str("# TYPE seichi_player_rank gauge\n")?;
+
+                for (player, data) in &data.0 {
+                    for kind in &presented_kinds {
+                        if let Some(rank) = data.rank(*kind).filter(|rank| *rank <= top_n) {
+                            write_player_labels(
+                                &mut result,
+                                r#"seichi_player_rank{uuid=""#,
+                                player,
+                                uuid_pseudonymizer,
+                                record_fragments.labels.suffix(*kind),
+                            );
+                            result.push_str(itoa::Buffer::new().format(rank));
+                            result.push('\n');
+                        }
+                    }
+                    cut_chunk_if_full(&mut chunks, &mut result);
+                }
+            }
+
+            if let Some(deltas) = deltas {
+                result.write_str(
+                    "# HELP player_data_delta Change of player metrics since the previous refresh, partitioned by uuid and kind\n",
+                )?;
+                result.write_str("# TYPE player_data_delta gauge\n")?;
+
+                for (player, delta) in &deltas.0 {
+                    for kind in &presented_kinds {
+                        write_delta_record(
+                            &mut result,
+                            player,
+                            uuid_pseudonymizer,
+                            record_fragments.labels.suffix(*kind),
+                            delta.get(*kind),
+                        );
+                    }
+                    cut_chunk_if_full(&mut chunks, &mut result);
+                }
+            }
+
+            if !result.is_empty() {
+                chunks.push(Bytes::from(result));
+            }
+
+            Ok(chunks)
+        }
+    }
+
+    fn const_error_response() -> (StatusCode, Response) {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Response::new(
+                body::boxed("Encountered internal server error. Please contact the server administrator to resolve the issue.".to_string())),
+        )
+    }
+
+    fn const_upstream_timeout_response() -> (StatusCode, Response) {
+        (
+            StatusCode::GATEWAY_TIMEOUT,
+            Response::new(body::boxed(
+                "Timed out while fetching data from the game data server.".to_string(),
+            )),
+        )
+    }
+
+    fn const_not_yet_rendered_response() -> (StatusCode, Response) {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Response::new(body::boxed(
+                "Player data has not been fetched yet.".to_string(),
+            )),
+        )
+    }
+
+    fn const_upstream_unavailable_response() -> (StatusCode, Response) {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Response::new(body::boxed(
+                "The game data server is currently unavailable.".to_string(),
+            )),
+        )
+    }
+
+    // Errors may be wrapped, e.g. when a refresh is shared between requests
+    fn is_caused_by<E: std::error::Error + 'static>(error: &anyhow::Error) -> bool {
+        error.chain().any(<dyn std::error::Error>::is::<E>)
+    }
+
+    fn error_response(error: &anyhow::Error) -> Response {
+        if is_caused_by::<UpstreamTimeoutError>(error) {
+            const_upstream_timeout_response().into_response()
+        } else if is_caused_by::<UpstreamUnavailableError>(error) {
+            const_upstream_unavailable_response().into_response()
+        } else {
+            const_error_response().into_response()
+        }
+    }
+
+    #[must_use]
+    pub fn handle_get_metrics(state: SharedAppState) -> impl Handler<(HeaderMap,)> {
+        // we need a separate handler function to create an error tracing span
+        #[tracing::instrument(skip_all)]
+        async fn handler(state: &SharedAppState, headers: &HeaderMap) -> Response {
+            let gzip = accepts_gzip(headers);
+            let started_at = Instant::now();
+
+            let result = if state
+                .rendering_config
+                .background_render_interval()
+                .is_some()
+            {
+                match state.rendered_exposition.latest() {
+                    Some(rendered) => exposition_response(&rendered, gzip),
+                    None => return const_not_yet_rendered_response().into_response(),
+                }
+            } else {
+                snapshot_use_case(state)
+                    .get_snapshot()
+                    .await
+                    .and_then(|snapshot| {
+                        state.rendered_exposition.get_or_render(
+                            &snapshot,
+                            state.repository.connection_state(),
+                            state.record_presentation(),
+                        )
+                    })
+                    .and_then(|rendered| exposition_response(&rendered, gzip))
+            };
+            enforce_cache_memory_budget(state);
+
+            match result {
+                Ok((response, body_size_bytes)) => {
+                    warn_if_slow_or_large(state, started_at.elapsed(), body_size_bytes);
+                    response
+                }
+                Err(e) => {
+                    state.error_log.error("failed to serve metrics", &e);
+                    error_response(&e)
+                }
+            }
+        }
+
+        |headers: HeaderMap| async move { handler(&state, &headers).await }
+    }
+
+    #[must_use]
+    pub fn handle_get_player(state: SharedAppState) -> impl Handler<(Path<String>,)> {
+        #[tracing::instrument(skip(state))]
+        async fn handler(state: &SharedAppState, uuid: &String) -> Response {
+            let uuid = match PlayerUuid::parse(uuid) {
+                Ok(uuid) => uuid,
+                Err(e) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        format!("Malformed player UUID: {e}"),
+                    )
+                        .into_response()
+                }
+            };
+
+            let reloadable_config = state.reloadable_config.load();
+            let use_case = GetPlayerDataUseCase {
+                repository: state.repository.clone(),
+                derived_stats_config: state.derived_stats_config.clone(),
+                fetch_config: reloadable_config.fetch_config.clone(),
+                cached_snapshot: state.snapshot_cache.fresh(&reloadable_config.cache_config),
+            };
+
+            match use_case.get_player_data(&uuid).await {
+                Ok(Some(stats)) => (
+                    StatusCode::OK,
+                    Json(presenter::present_player_stats_as_json(&uuid, &stats)),
+                )
+                    .into_response(),
+                Ok(None) => (StatusCode::NOT_FOUND, "Unknown player").into_response(),
+                Err(e) => {
+                    state.error_log.error("failed to serve player data", &e);
+                    error_response(&e)
+                }
+            }
+        }
+
+        |Path(uuid): Path<String>| async move { handler(&state, &uuid).await }
+    }
+
+    #[must_use]
+    pub fn handle_get_summary(state: SharedAppState) -> impl Handler<()> {
+        #[tracing::instrument(skip_all)]
+        async fn handler(state: &SharedAppState) -> Response {
+            match snapshot_use_case(state).get_snapshot().await {
+                Ok(snapshot) => (
+                    StatusCode::OK,
+                    Json(presenter::present_population_summary_as_json(
+                        &snapshot.summary,
+                    )),
+                )
+                    .into_response(),
+                Err(e) => {
+                    state.error_log.error("failed to serve the summary", &e);
+                    error_response(&e)
+                }
+            }
+        }
+
+        || async move { handler(&state).await }
+    }
+
+    #[must_use]
+    pub fn handle_get_groups(state: SharedAppState) -> impl Handler<()> {
+        #[tracing::instrument(skip_all)]
+        async fn handler(state: &SharedAppState) -> Response {
+            if state.group_membership_repository.is_none() {
+                return (
+                    StatusCode::NOT_FOUND,
+                    "Group memberships are not configured",
+                )
+                    .into_response();
+            }
+
+            let snapshot = match snapshot_use_case(state).get_snapshot().await {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    state.error_log.error("failed to serve group totals", &e);
+                    return error_response(&e);
+                }
+            };
+
+            let Some(group_totals) = &snapshot.group_totals else {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Group totals are not available yet",
+                )
+                    .into_response();
+            };
+
+            (
+                StatusCode::OK,
+                Json(presenter::present_group_totals_as_json(
+                    &snapshot.fetched,
+                    group_totals,
+                )),
+            )
+                .into_response()
+        }
+
+        || async move { handler(&state).await }
+    }
+
+    #[must_use]
+    pub fn handle_get_internal_metrics(state: SharedAppState) -> impl Handler<()> {
+        #[tracing::instrument(skip_all)]
+        async fn handler(state: &SharedAppState) -> Response {
+            use prometheus::Encoder;
+
+            let mut buffer = Vec::new();
+            match prometheus::TextEncoder::new().encode(&state.self_metrics.gather(), &mut buffer) {
+                Ok(()) => (StatusCode::OK, buffer).into_response(),
+                Err(e) => {
+                    tracing::error!("{:?}", e);
+                    const_error_response().into_response()
+                }
+            }
+        }
+
+        || async move { handler(&state).await }
+    }
+
+    #[must_use]
+    pub fn handle_post_admin_reload(state: SharedAppState) -> impl Handler<()> {
+        #[tracing::instrument(skip_all)]
+        async fn handler(state: &SharedAppState) -> Response {
+            match reload_config(state) {
+                Ok(()) => (StatusCode::OK, "reloaded").into_response(),
+                Err(e) => {
+                    tracing::error!("failed to reload the configuration: {:?}", e);
+                    (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")).into_response()
+                }
+            }
+        }
+
+        || async move { handler(&state).await }
+    }
+
+    #[must_use]
+    pub fn handle_get_liveness() -> impl Handler<()> {
+        #[tracing::instrument]
+        async fn handler() -> (StatusCode, &'static str) {
+            (StatusCode::OK, "OK")
+        }
+
+        handler
+    }
+
+    #[must_use]
+    pub fn handle_get_readiness(state: SharedAppState) -> impl Handler<()> {
+        #[tracing::instrument(skip_all)]
+        async fn handler(state: &SharedAppState) -> Response {
+            let readiness = state.repository.check_readiness().await;
+            if let Err(e) = &readiness {
+                tracing::warn!("not ready: {:?}", e);
+            }
+
+            let status = if readiness.is_ok() {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            let detail = serde_json::json!({
+                "ready": readiness.is_ok(),
+                "health": state.health.state().as_str(),
+                "upstream_connection": state.repository.connection_state().as_str(),
+            });
+
+            (status, Json(detail)).into_response()
+        }
+
+        || async move { handler(&state).await }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use axum::routing::post;
+        use axum::{middleware, Router};
+        use tower::Service;
+
+        async fn status_of(request: Request<body::Body>, max_bytes: u64) -> StatusCode {
+            let mut router = Router::new()
+                .route("/", post(|| async { "accepted" }))
+                .route_layer(middleware::from_fn(move |request, next| {
+                    limit_request_body(max_bytes, request, next)
+                }));
+
+            router.call(request).await.unwrap().status()
+        }
+
+        fn request(headers: &[(header::HeaderName, &str)], body: &str) -> Request<body::Body> {
+            let mut request = Request::post("/");
+            for (name, value) in headers {
+                request = request.header(name, *value);
+            }
+
+            request.body(body::Body::from(body.to_owned())).unwrap()
+        }
+
+        #[tokio::test]
+        async fn accept_bodies_within_the_limit() {
+            let request = request(&[(header::CONTENT_LENGTH, "5")], "hello");
+
+            assert_eq!(status_of(request, 5).await, StatusCode::OK);
+        }
+
+        #[tokio::test]
+        async fn reject_bodies_declared_larger_than_the_limit() {
+            let request = request(&[(header::CONTENT_LENGTH, "5")], "hello");
+
+            assert_eq!(status_of(request, 4).await, StatusCode::PAYLOAD_TOO_LARGE);
+        }
+
+        #[tokio::test]
+        async fn reject_bodies_larger_than_the_limit_whatever_they_declare() {
+            let request = request(&[], "hello");
+
+            assert_eq!(status_of(request, 4).await, StatusCode::PAYLOAD_TOO_LARGE);
+        }
+
+        #[tokio::test]
+        async fn reject_both_a_content_length_and_a_transfer_encoding() {
+            let request = request(
+                &[
+                    (header::CONTENT_LENGTH, "0"),
+                    (header::TRANSFER_ENCODING, "chunked"),
+                ],
+                "",
+            );
+
+            assert_eq!(status_of(request, 1024).await, StatusCode::BAD_REQUEST);
+        }
+
+        #[tokio::test]
+        async fn reject_transfer_encoded_bodies_where_no_body_is_accepted() {
+            let request = request(&[(header::TRANSFER_ENCODING, "chunked")], "");
+
+            assert_eq!(status_of(request, 0).await, StatusCode::PAYLOAD_TOO_LARGE);
+        }
+    }
+}
+
+pub mod infra_repository_impls {
+    #[allow(dead_code)]
+    #[allow(clippy::nursery, clippy::pedantic)]
+    mod buf_generated {
+        #![allow(clippy::derive_partial_eq_without_eq)]
+        include!("gen/mod.rs");
+    }
+
+    pub mod config {
+        use super::discovery::{EndpointDiscovery, UpstreamEndpoints};
+        use super::retry::RetryPolicy;
+        use crate::config::{ConfigSources, Settings};
+        use crate::domain::GameServer;
+        use anyhow::{anyhow, Context};
+        use std::time::Duration;
+        use tonic::codec::CompressionEncoding;
+        use tonic::codegen::http::Uri;
+        use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+        const fn default_upstream_retry_max_attempts() -> u32 {
+            3
+        }
+
+        const fn default_upstream_retry_initial_backoff_millis() -> u64 {
+            200
+        }
+
+        const fn default_upstream_retry_max_backoff_millis() -> u64 {
+            5000
+        }
+
+        const fn default_upstream_retry_jitter_ratio() -> f64 {
+            0.2
+        }
+
+        const fn default_upstream_rpc_timeout_millis() -> u64 {
+            30_000
+        }
+
+        const fn default_upstream_health_check_interval_millis() -> u64 {
+            10_000
+        }
+
+        const fn default_upstream_channel_pool_size() -> usize {
+            1
+        }
+
+        const fn default_upstream_max_decoding_message_size() -> usize {
+            64 * 1024 * 1024
+        }
+
+        /// Source of player data.
+        #[derive(
+            serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq,
+        )]
+        #[serde(rename_all = "snake_case")]
+        pub enum RepositoryMode {
+            /// The game data server, reached over gRPC.
+            #[default]
+            Grpc,
+            /// Deterministic fake players generated in process.
+            Stub,
+            /// A local JSON or CSV file at `repository_file_path`.
+            File,
+        }
+
+        impl RepositoryMode {
+            #[must_use]
+            pub const fn as_str(self) -> &'static str {
+                match self {
+                    Self::Grpc => "grpc",
+                    Self::Stub => "stub",
+                    Self::File => "file",
+                }
+            }
+        }
+
+        /// Source of the groups, such as guilds, that players are members of.
+        #[derive(
+            serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq,
+        )]
+        #[serde(rename_all = "snake_case")]
+        pub enum GroupMembershipSource {
+            /// Groups are not totalled.
+            #[default]
+            Disabled,
+            /// The game data server, or the first of the game servers.
+            Upstream,
+            /// A local JSON or CSV file at `group_membership_file_path`.
+            File,
+        }
+
+        const fn default_stub_player_count() -> u64 {
+            1000
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+        pub struct RepositoryConfig {
+            #[serde(default)]
+            pub repository_mode: RepositoryMode,
+            /// Number of players generated in the `stub` mode.
+            #[serde(default = "default_stub_player_count")]
+            pub stub_player_count: u64,
+            /// File read in the `file` mode.
+            pub repository_file_path: Option<String>,
+            #[serde(default)]
+            pub group_membership_source: GroupMembershipSource,
+            /// File with a `uuid` and a `group` column, read in the `file` group membership source.
+            pub group_membership_file_path: Option<String>,
+        }
+
+        impl Settings for RepositoryConfig {}
+
+        impl RepositoryConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let config = sources.extract::<Self>()?;
+
+                if config.repository_mode == RepositoryMode::File
+                    && config.repository_file_path.is_none()
+                {
+                    return Err(sources.invalid(
+                        "REPOSITORY_FILE_PATH",
+                        "is required when REPOSITORY_MODE is file",
+                    ));
+                }
+                match config.group_membership_source {
+                    GroupMembershipSource::Upstream
+                        if config.repository_mode != RepositoryMode::Grpc =>
+                    {
+                        return Err(sources.invalid(
+                            "GROUP_MEMBERSHIP_SOURCE",
+                            "upstream is only available when REPOSITORY_MODE is grpc",
+                        ));
+                    }
+                    GroupMembershipSource::File if config.group_membership_file_path.is_none() => {
+                        return Err(sources.invalid(
+                            "GROUP_MEMBERSHIP_FILE_PATH",
+                            "is required when GROUP_MEMBERSHIP_SOURCE is file",
+                        ));
+                    }
+                    _ => {}
+                }
+
+                Ok(config)
+            }
+        }
+
+        fn parse_compression_encoding(value: &str) -> anyhow::Result<CompressionEncoding> {
+            match value.to_ascii_lowercase().as_str() {
+                "gzip" => Ok(CompressionEncoding::Gzip),
+                "zstd" => Err(anyhow!(
+                    "zstd compression is not supported by the gRPC client in use, use gzip instead"
+                )),
+                other => Err(anyhow!("Unknown compression encoding {other}")),
+            }
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+        pub struct GrpcClientConfig {
+            /// URL of the game data server. Multiple replicas can be listed separated by commas.
+            #[serde(default)]
+            pub game_data_server_grpc_endpoint_url: String,
+            /// Game servers whose statistics are fetched apart and tagged with the server they
+            /// come from, as `name=url` pairs separated by commas (e.g.
+            /// `main=http://main:8080,sub=http://sub:8080`). Takes the place of
+            /// `game_data_server_grpc_endpoint_url` when set. Every other setting applies to each
+            /// server.
+            #[serde(default)]
+            pub game_servers: Vec<String>,
+            /// Total number of attempts per RPC, including the first one.
+            #[serde(default = "default_upstream_retry_max_attempts")]
+            pub upstream_retry_max_attempts: u32,
+            #[serde(default = "default_upstream_retry_initial_backoff_millis")]
+            pub upstream_retry_initial_backoff_millis: u64,
+            #[serde(default = "default_upstream_retry_max_backoff_millis")]
+            pub upstream_retry_max_backoff_millis: u64,
+            /// Each backoff is randomly scaled within `1 ± upstream_retry_jitter_ratio`.
+            #[serde(default = "default_upstream_retry_jitter_ratio")]
+            pub upstream_retry_jitter_ratio: f64,
+            /// Deadline applied to each attempt of an upstream RPC.
+            #[serde(default = "default_upstream_rpc_timeout_millis")]
+            pub upstream_rpc_timeout_millis: u64,
+            /// Delay after which a second, hedged attempt of an RPC is issued while the first one
+            /// is still pending, taking whichever succeeds first. Hedging is disabled when unset.
+            pub upstream_hedge_delay_millis: Option<u64>,
+            /// Resolve the host of the endpoint URL and balance over every address it resolves to,
+            /// instead of treating the URL as a single endpoint.
+            #[serde(default)]
+            pub upstream_dns_discovery: bool,
+            /// Interval at which the connection supervisor probes the upstream.
+            #[serde(default = "default_upstream_health_check_interval_millis")]
+            pub upstream_health_check_interval_millis: u64,
+            /// PEM file of the CA certificate used to verify the upstream.
+            /// The system trust roots are used when unset.
+            pub upstream_tls_ca_cert_path: Option<String>,
+            /// Domain name used for SNI and certificate verification, if it differs from the URL host.
+            pub upstream_tls_domain_name: Option<String>,
+            /// PEM files of the client certificate and key, presented for mutual TLS.
+            pub upstream_tls_client_cert_path: Option<String>,
+            pub upstream_tls_client_key_path: Option<String>,
+            /// Compression applied to requests sent to the upstream (`gzip`).
+            pub upstream_send_compression: Option<String>,
+            /// Compression the upstream is allowed to apply to its responses (`gzip`).
+            pub upstream_accept_compression: Option<String>,
+            /// Number of channels, each with its own connections to every replica, that requests
+            /// are spread over in a round-robin fashion.
+            #[serde(default = "default_upstream_channel_pool_size")]
+            pub upstream_channel_pool_size: usize,
+            /// Largest response, in bytes, accepted from the upstream.
+            #[serde(default = "default_upstream_max_decoding_message_size")]
+            pub upstream_max_decoding_message_size: usize,
+            /// Interval of HTTP/2 pings sent to keep the upstream connection alive.
+            pub upstream_http2_keep_alive_interval_millis: Option<u64>,
+            /// Time to wait for a keepalive ping to be acknowledged before closing the connection.
+            pub upstream_keep_alive_timeout_millis: Option<u64>,
+            /// Token sent to the upstream as `authorization: Bearer <token>` metadata.
+            pub upstream_auth_token: Option<String>,
+        }
+
+        impl Settings for GrpcClientConfig {
+            const SECRETS: &'static [&'static str] = &["upstream_auth_token"];
+        }
+
+        impl GrpcClientConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let config = sources.extract::<Self>()?;
+
+                if config.game_servers.is_empty() {
+                    if config.game_data_server_grpc_endpoint_url.is_empty() {
+                        return Err(sources.invalid(
+                            "GAME_DATA_SERVER_GRPC_ENDPOINT_URL",
+                            "is required unless GAME_SERVERS is set",
+                        ));
+                    }
+                } else {
+                    let servers = config
+                        .game_servers()
+                        .map_err(|e| sources.invalid("GAME_SERVERS", format!("{e:#}")))?;
+                    let mut names = servers
+                        .iter()
+                        .map(|(server, _)| server.as_str())
+                        .collect::<Vec<_>>();
+                    names.sort_unstable();
+                    if let Some([name, _]) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+                        return Err(
+                            sources.invalid("GAME_SERVERS", format!("{name} is listed twice"))
+                        );
+                    }
+                }
+
+                Ok(config)
+            }
+
+            /// The configuration of each of `game_servers`, which only differ by their URL.
+            #[allow(clippy::missing_errors_doc)]
+            pub fn game_servers(&self) -> anyhow::Result<Vec<(GameServer, Self)>> {
+                self.game_servers
+                    .iter()
+                    .map(|entry| {
+                        let (name, url) = entry
+                            .split_once('=')
+                            .ok_or_else(|| anyhow!("Expected name=url, got {entry}"))?;
+                        let config = Self {
+                            game_data_server_grpc_endpoint_url: url.trim().to_string(),
+                            game_servers: Vec::new(),
+                            ..self.clone()
+                        };
+
+                        Ok((GameServer::new(name.trim())?, config))
+                    })
+                    .collect()
+            }
+
+            #[must_use]
+            pub fn retry_policy(&self) -> RetryPolicy {
+                RetryPolicy {
+                    max_attempts: self.upstream_retry_max_attempts.max(1),
+                    initial_backoff: Duration::from_millis(
+                        self.upstream_retry_initial_backoff_millis,
+                    ),
+                    max_backoff: Duration::from_millis(self.upstream_retry_max_backoff_millis),
+                    jitter_ratio: self.upstream_retry_jitter_ratio.clamp(0.0, 1.0),
+                }
+            }
+
+            #[must_use]
+            pub const fn rpc_timeout(&self) -> Duration {
+                Duration::from_millis(self.upstream_rpc_timeout_millis)
+            }
+
+            pub fn hedge_delay(&self) -> Option<Duration> {
+                self.upstream_hedge_delay_millis.map(Duration::from_millis)
+            }
+
+            #[must_use]
+            pub const fn health_check_interval(&self) -> Duration {
+                Duration::from_millis(self.upstream_health_check_interval_millis)
+            }
+
+            #[allow(clippy::missing_errors_doc)]
+            pub fn send_compression(&self) -> anyhow::Result<Option<CompressionEncoding>> {
+                self.upstream_send_compression
+                    .as_deref()
+                    .map(parse_compression_encoding)
+                    .transpose()
+            }
+
+            #[allow(clippy::missing_errors_doc)]
+            pub fn accept_compression(&self) -> anyhow::Result<Option<CompressionEncoding>> {
+                self.upstream_accept_compression
+                    .as_deref()
+                    .map(parse_compression_encoding)
+                    .transpose()
+            }
+
+            fn endpoint_urls(&self) -> Vec<String> {
+                self.game_data_server_grpc_endpoint_url
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+
+            #[allow(clippy::missing_errors_doc)]
+            pub fn upstream_endpoints(&self) -> anyhow::Result<UpstreamEndpoints> {
+                let urls = self.endpoint_urls();
+                let mut tls_config = self.tls_config()?;
+
+                let discovery = if self.upstream_dns_discovery {
+                    let [url] = urls.as_slice() else {
+                        return Err(anyhow!(
+                            "Exactly one endpoint URL is required for DNS discovery, got {urls:?}"
+                        ));
+                    };
+                    let uri: Uri = url.parse()?;
+
+                    // Replicas are dialed by address, so the certificate has to be verified
+                    // against the host name of the URL
+                    if let (Some(config), None, Some(host)) = (
+                        tls_config.take(),
+                        &self.upstream_tls_domain_name,
+                        uri.host(),
+                    ) {
+                        tls_config = Some(config.domain_name(host));
+                    }
+
+                    EndpointDiscovery::Dns(uri)
+                } else if urls.is_empty() {
+                    return Err(anyhow!("No endpoint URL is configured"));
+                } else {
+                    EndpointDiscovery::Static(urls)
+                };
+
+                Ok(UpstreamEndpoints {
+                    discovery,
+                    tls_config,
+                    http2_keep_alive_interval: self
+                        .upstream_http2_keep_alive_interval_millis
+                        .map(Duration::from_millis),
+                    keep_alive_timeout: self
+                        .upstream_keep_alive_timeout_millis
+                        .map(Duration::from_millis),
+                })
+            }
+
+            /// Builds the TLS configuration for the upstream connection. TLS is used when an
+            /// endpoint URL has the `https` scheme or when any TLS option is set.
+            #[allow(clippy::missing_errors_doc)]
+            pub fn tls_config(&self) -> anyhow::Result<Option<ClientTlsConfig>> {
+                let uses_tls = self
+                    .endpoint_urls()
+                    .iter()
+                    .any(|url| url.starts_with("https://"))
+                    || self.upstream_tls_ca_cert_path.is_some()
+                    || self.upstream_tls_domain_name.is_some()
+                    || self.upstream_tls_client_cert_path.is_some()
+                    || self.upstream_tls_client_key_path.is_some();
+
+                if !uses_tls {
+                    return Ok(None);
+                }
+
+                let mut tls_config = ClientTlsConfig::new();
+
+                if let Some(ca_cert_path) = &self.upstream_tls_ca_cert_path {
+                    let ca_cert = std::fs::read(ca_cert_path)
+                        .with_context(|| format!("Failed to read CA certificate {ca_cert_path}"))?;
+                    tls_config = tls_config.ca_certificate(Certificate::from_pem(ca_cert));
+                }
+
+                if let Some(domain_name) = &self.upstream_tls_domain_name {
+                    tls_config = tls_config.domain_name(domain_name);
+                }
+
+                match (
+                    &self.upstream_tls_client_cert_path,
+                    &self.upstream_tls_client_key_path,
+                ) {
+                    (Some(cert_path), Some(key_path)) => {
+                        let cert = std::fs::read(cert_path).with_context(|| {
+                            format!("Failed to read client certificate {cert_path}")
+                        })?;
+                        let key = std::fs::read(key_path)
+                            .with_context(|| format!("Failed to read client key {key_path}"))?;
+                        tls_config = tls_config.identity(Identity::from_pem(cert, key));
+                    }
+                    (None, None) => {}
+                    _ => {
+                        return Err(anyhow!(
+                            "UPSTREAM_TLS_CLIENT_CERT_PATH and UPSTREAM_TLS_CLIENT_KEY_PATH must be set together"
+                        ))
+                    }
+                }
+
+                Ok(Some(tls_config))
+            }
+        }
+    }
+
+    mod discovery {
+        use anyhow::anyhow;
+        use std::time::Duration;
+        use tonic::codegen::http::Uri;
+        use tonic::transport::{ClientTlsConfig, Endpoint};
+
+        /// How the set of upstream replicas is determined.
+        #[derive(Debug, Clone)]
+        pub enum EndpointDiscovery {
+            /// A fixed list of endpoint URLs.
+            Static(Vec<String>),
+            /// Every address the host of the URL resolves to, re-resolved on each discovery.
+            Dns(Uri),
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct UpstreamEndpoints {
+            pub discovery: EndpointDiscovery,
+            pub tls_config: Option<ClientTlsConfig>,
+            pub http2_keep_alive_interval: Option<Duration>,
+            pub keep_alive_timeout: Option<Duration>,
+        }
+
+        impl UpstreamEndpoints {
+            fn configure(&self, mut endpoint: Endpoint) -> anyhow::Result<Endpoint> {
+                if let Some(interval) = self.http2_keep_alive_interval {
+                    endpoint = endpoint.http2_keep_alive_interval(interval);
+                }
+                if let Some(timeout) = self.keep_alive_timeout {
+                    endpoint = endpoint.keep_alive_timeout(timeout);
+                }
+
+                Ok(match &self.tls_config {
+                    Some(tls_config) => endpoint.tls_config(tls_config.clone())?,
+                    None => endpoint,
+                })
+            }
+
+            /// Lists the currently known replicas, keyed by a string identifying each of them.
+            pub async fn discover(&self) -> anyhow::Result<Vec<(String, Endpoint)>> {
+                match &self.discovery {
+                    EndpointDiscovery::Static(urls) => urls
+                        .iter()
+                        .map(|url| {
+                            let endpoint = self.configure(Endpoint::from_shared(url.clone())?)?;
+                            Ok((url.clone(), endpoint))
+                        })
+                        .collect(),
+                    EndpointDiscovery::Dns(uri) => {
+                        let host = uri
+                            .host()
+                            .ok_or_else(|| anyhow!("Endpoint URL {uri} has no host"))?;
+                        let scheme = uri.scheme_str().unwrap_or("http");
+                        let port =
+                            uri.port_u16()
+                                .unwrap_or(if scheme == "https" { 443 } else { 80 });
+
+                        tokio::net::lookup_host((host, port))
+                            .await?
+                            .map(|address| {
+                                // Requests keep the original URL as their origin so that the
+                                // authority seen by the upstream does not change per replica
+                                let endpoint =
+                                    Endpoint::from_shared(format!("{scheme}://{address}"))?
+                                        .origin(uri.clone());
+
+                                Ok((address.to_string(), self.configure(endpoint)?))
+                            })
+                            .collect()
+                    }
+                }
+            }
+        }
+    }
+
+    mod retry {
+        use futures::future::{self, Either};
+        use rand::Rng;
+        use std::future::Future;
+        use std::time::Duration;
+        use tonic::Code;
+
+        #[derive(Debug, Clone)]
+        pub struct RetryPolicy {
+            pub max_attempts: u32,
+            pub initial_backoff: Duration,
+            pub max_backoff: Duration,
+            pub jitter_ratio: f64,
+        }
+
+        /// Statuses that are worth retrying because the same request may succeed later.
+        const fn is_transient(code: Code) -> bool {
+            matches!(
+                code,
+                Code::Unavailable
+                    | Code::DeadlineExceeded
+                    | Code::ResourceExhausted
+                    | Code::Aborted
+            )
+        }
+
+        impl RetryPolicy {
+            fn backoff_after(&self, failed_attempts: u32) -> Duration {
+                let exponent = failed_attempts.saturating_sub(1);
+                let backoff = self
+                    .initial_backoff
+                    .saturating_mul(2u32.saturating_pow(exponent))
+                    .min(self.max_backoff);
+
+                if self.jitter_ratio > 0.0 {
+                    let factor = rand::thread_rng()
+                        .gen_range((1.0 - self.jitter_ratio)..=(1.0 + self.jitter_ratio));
+                    backoff.mul_f64(factor)
+                } else {
+                    backoff
+                }
+            }
+
+            pub async fn run<T, F, Fut>(
+                &self,
+                rpc_name: &'static str,
+                mut call: F,
+            ) -> Result<T, tonic::Status>
+            where
+                F: FnMut() -> Fut + Send,
+                Fut: Future<Output = Result<T, tonic::Status>> + Send,
+                T: Send,
+            {
+                let mut attempts = 1;
+
+                loop {
+                    match call().await {
+                        Err(status)
+                            if attempts < self.max_attempts && is_transient(status.code()) =>
+                        {
+                            let backoff = self.backoff_after(attempts);
+                            tracing::warn!(
+                                "{} failed with transient status {:?} (attempt {}/{}), retrying in {:?}",
+                                rpc_name,
+                                status.code(),
+                                attempts,
+                                self.max_attempts,
+                                backoff
+                            );
+
+                            tokio::time::sleep(backoff).await;
+                            attempts += 1;
+                        }
+                        result => return result,
+                    }
+                }
+            }
+        }
+
+        /// Runs `attempt`, and if it has not completed after `hedge_delay`, runs it a second time
+        /// concurrently. The first successful response wins; if the attempt that completes first
+        /// fails, the other one is awaited instead.
+        pub async fn hedged<T, F, Fut>(
+            rpc_name: &'static str,
+            hedge_delay: Option<Duration>,
+            attempt: &F,
+        ) -> Result<T, tonic::Status>
+        where
+            F: Fn() -> Fut + Sync,
+            Fut: Future<Output = Result<T, tonic::Status>> + Send,
+            T: Send,
+        {
+            let mut primary = Box::pin(attempt());
+            let Some(hedge_delay) = hedge_delay else {
+                return primary.await;
+            };
+
+            tokio::select! {
+                result = &mut primary => return result,
+                () = tokio::time::sleep(hedge_delay) => {}
+            }
+
+            tracing::debug!(
+                "{} did not complete within {:?}, issuing a hedged attempt",
+                rpc_name,
+                hedge_delay
+            );
+            let hedge = Box::pin(attempt());
+
+            let (Either::Left((result, remaining)) | Either::Right((result, remaining))) =
+                future::select(primary, hedge).await;
+            match result {
+                Ok(response) => Ok(response),
+                Err(_) => remaining.await,
+            }
+        }
+    }
+
+    mod buf_generated_to_domain {
+        use super::buf_generated::gigantic_minecraft::seichi_game_data::v1 as generated;
+        use crate::domain;
+        use crate::domain::PlayerUuid;
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        fn into_domain_player(p: &generated::Player) -> anyhow::Result<domain::Player> {
+            Ok(domain::Player::new(PlayerUuid::parse(&p.uuid)?))
+        }
+
+        #[tracing::instrument(skip_all)]
+        fn extract_domain_player(
+            player: Option<generated::Player>,
+        ) -> anyhow::Result<domain::Player> {
+            let player = player.ok_or_else(|| anyhow::anyhow!("Player field not set"))?;
+
+            into_domain_player(&player)
+        }
+
+        /// A record returned by the upstream, pairing a player with a value of the player.
+        pub trait GeneratedPlayerRecord: std::fmt::Debug {
+            type Value;
+
+            fn into_parts(self) -> anyhow::Result<(Option<generated::Player>, Self::Value)>;
+        }
+
+        impl GeneratedPlayerRecord for generated::PlayerBreakCount {
+            type Value = u64;
+
+            fn into_parts(self) -> anyhow::Result<(Option<generated::Player>, u64)> {
+                Ok((self.player, self.break_count))
+            }
+        }
+
+        impl GeneratedPlayerRecord for generated::PlayerBuildCount {
+            type Value = u64;
+
+            fn into_parts(self) -> anyhow::Result<(Option<generated::Player>, u64)> {
+                Ok((self.player, self.build_count))
+            }
+        }
+
+        impl GeneratedPlayerRecord for generated::PlayerPlayTicks {
+            type Value = u64;
+
+            fn into_parts(self) -> anyhow::Result<(Option<generated::Player>, u64)> {
+                Ok((self.player, self.play_ticks))
+            }
+        }
+
+        impl GeneratedPlayerRecord for generated::PlayerVoteCount {
+            type Value = u64;
+
+            fn into_parts(self) -> anyhow::Result<(Option<generated::Player>, u64)> {
+                Ok((self.player, self.vote_count))
+            }
+        }
+
+        impl GeneratedPlayerRecord for generated::PlayerLoginCount {
+            type Value = u64;
+
+            fn into_parts(self) -> anyhow::Result<(Option<generated::Player>, u64)> {
+                Ok((self.player, self.login_count))
+            }
+        }
+
+        impl GeneratedPlayerRecord for generated::PlayerAfkTicks {
+            type Value = u64;
+
+            fn into_parts(self) -> anyhow::Result<(Option<generated::Player>, u64)> {
+                Ok((self.player, self.afk_ticks))
+            }
+        }
+
+        impl GeneratedPlayerRecord for generated::PlayerLastQuit {
+            type Value = SystemTime;
+
+            fn into_parts(self) -> anyhow::Result<(Option<generated::Player>, SystemTime)> {
+                let last_quit = self
+                    .last_quit
+                    .ok_or_else(|| anyhow::anyhow!("Last quit field not set"))?;
+                let since_epoch = Duration::new(
+                    u64::try_from(last_quit.seconds)?,
+                    u32::try_from(last_quit.nanos)?,
+                );
+
+                Ok((self.player, UNIX_EPOCH + since_epoch))
+            }
+        }
+
+        /// Play ticks together with the name their record carries, if it is set.
+        #[derive(Debug)]
+        pub struct NamedPlayTicks(pub generated::PlayerPlayTicks);
+
+        impl GeneratedPlayerRecord for NamedPlayTicks {
+            type Value = (u64, Option<String>);
+
+            fn into_parts(
+                self,
+            ) -> anyhow::Result<(Option<generated::Player>, (u64, Option<String>))> {
+                let name = self
+                    .0
+                    .player
+                    .as_ref()
+                    .map(|player| player.last_known_name.clone())
+                    .filter(|name| !name.is_empty());
+
+                Ok((self.0.player, (self.0.play_ticks, name)))
+            }
+        }
+
+        impl GeneratedPlayerRecord for generated::PlayerGuildMembership {
+            type Value = String;
+
+            fn into_parts(self) -> anyhow::Result<(Option<generated::Player>, String)> {
+                if self.guild_name.is_empty() {
+                    return Err(anyhow::anyhow!("Guild name not set"));
+                }
+
+                Ok((self.player, self.guild_name))
+            }
+        }
+
+        #[tracing::instrument(skip_all)]
+        pub fn try_into_domain_record<R: GeneratedPlayerRecord>(
+            value: R,
+        ) -> anyhow::Result<(domain::Player, R::Value)> {
+            let (player, value) = value.into_parts()?;
+
+            Ok((extract_domain_player(player)?, value))
+        }
+    }
+
+    mod auth {
+        use tonic::metadata::{Ascii, MetadataValue};
+        use tonic::service::Interceptor;
+
+        /// Attaches `authorization: Bearer <token>` metadata to every request, if a token is set.
+        #[derive(Clone, Default)]
+        pub struct BearerTokenInterceptor {
+            authorization: Option<MetadataValue<Ascii>>,
+        }
+
+        // The token must not end up in logs through the spans recording the repository
+        impl std::fmt::Debug for BearerTokenInterceptor {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct("BearerTokenInterceptor")
+                    .field("has_token", &self.authorization.is_some())
+                    .finish()
+            }
+        }
+
+        impl BearerTokenInterceptor {
+            pub fn new(token: Option<&str>) -> anyhow::Result<Self> {
+                let authorization = token
+                    .map(|token| format!("Bearer {}", token.trim()).parse())
+                    .transpose()?;
+
+                Ok(Self { authorization })
+            }
+        }
+
+        impl Interceptor for BearerTokenInterceptor {
+            fn call(
+                &mut self,
+                mut request: tonic::Request<()>,
+            ) -> Result<tonic::Request<()>, tonic::Status> {
+                if let Some(authorization) = &self.authorization {
+                    request
+                        .metadata_mut()
+                        .insert("authorization", authorization.clone());
+                }
+
+                Ok(request)
+            }
+        }
+    }
+
+    pub mod telemetry {
+        use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+        use std::time::Duration;
+
+        const RPC_DURATION_BUCKETS: [f64; 10] =
+            [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0];
+
+        /// Self-metrics about the RPCs issued to the game data server.
+        #[derive(Debug, Clone)]
+        pub struct UpstreamTelemetry {
+            rpc_duration: HistogramVec,
+            response_bytes: HistogramVec,
+            rpc_errors: IntCounterVec,
+            invalid_records: IntCounterVec,
+        }
+
+        impl UpstreamTelemetry {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn register(registry: &Registry) -> anyhow::Result<Self> {
+                let rpc_duration = HistogramVec::new(
+                    HistogramOpts::new(
+                        "seichi_translator_upstream_rpc_duration_seconds",
+                        "Time taken by upstream RPCs, including retries",
+                    )
+                    .buckets(RPC_DURATION_BUCKETS.to_vec()),
+                    &["rpc"],
+                )?;
+                let response_bytes = HistogramVec::new(
+                    HistogramOpts::new(
+                        "seichi_translator_upstream_response_bytes",
+                        "Encoded size of successful upstream responses",
+                    )
+                    .buckets(prometheus::exponential_buckets(1024.0, 4.0, 10)?),
+                    &["rpc"],
+                )?;
+                let rpc_errors = IntCounterVec::new(
+                    Opts::new(
+                        "seichi_translator_upstream_rpc_errors_total",
+                        "Upstream RPCs that failed after exhausting retries, by status code",
+                    ),
+                    &["rpc", "code"],
+                )?;
+
+                let invalid_records = IntCounterVec::new(
+                    Opts::new(
+                        "seichi_invalid_records_total",
+                        "Upstream records that were dropped because they could not be converted",
+                    ),
+                    &["rpc"],
+                )?;
+
+                registry.register(Box::new(rpc_duration.clone()))?;
+                registry.register(Box::new(response_bytes.clone()))?;
+                registry.register(Box::new(rpc_errors.clone()))?;
+                registry.register(Box::new(invalid_records.clone()))?;
+
+                Ok(Self {
+                    rpc_duration,
+                    response_bytes,
+                    rpc_errors,
+                    invalid_records,
+                })
+            }
+
+            pub fn record_invalid_record(&self, rpc_name: &'static str) {
+                self.invalid_records.with_label_values(&[rpc_name]).inc();
+            }
+
+            pub fn observe<T: prost::Message>(
+                &self,
+                rpc_name: &'static str,
+                elapsed: Duration,
+                result: &Result<tonic::Response<T>, tonic::Status>,
+            ) {
+                self.rpc_duration
+                    .with_label_values(&[rpc_name])
+                    .observe(elapsed.as_secs_f64());
+
+                match result {
+                    Ok(response) => {
+                        #[allow(clippy::cast_precision_loss)]
+                        self.response_bytes
+                            .with_label_values(&[rpc_name])
+                            .observe(response.get_ref().encoded_len() as f64);
+                    }
+                    Err(status) => self
+                        .rpc_errors
+                        .with_label_values(&[rpc_name, &format!("{:?}", status.code())])
+                        .inc(),
+                }
+            }
+        }
+    }
+
+    pub mod stub {
+        use crate::domain::{
+            Player, PlayerDataKind, PlayerDataRepository, PlayerDataStream, PlayerUuid,
+            UpstreamConnectionState,
+        };
+        use futures::stream::{self, StreamExt};
+        use std::time::{Duration, SystemTime};
+
+        /// Fake players were last seen within this long before the fetch.
+        const FAKE_LAST_SEEN_MAX_AGE: Duration = Duration::from_hours(30 * 24);
+
+        /// In-process repository generating deterministic fake players, for local development
+        /// without access to the game data server.
+        #[derive(Debug, Clone)]
+        pub struct StubPlayerDataRepository {
+            player_count: u64,
+        }
+
+        /// Derives a stable pseudo-random value from the index of a player and a salt,
+        /// so that every kind does not look alike.
+        const fn fake_value(index: u64, salt: u64, max: u64) -> u64 {
+            index
+                .wrapping_add(salt)
+                .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                .rotate_left(17)
+                % max
+        }
+
+        /// Upper bound of the fake values of `kind`, so that they fall in realistic ranges.
+        const fn fake_value_max(kind: PlayerDataKind) -> u64 {
+            match kind {
+                PlayerDataKind::BreakCount => 10_000_000,
+                PlayerDataKind::BuildCount => 1_000_000,
+                PlayerDataKind::PlayTicks => 100_000_000,
+                PlayerDataKind::VoteCount => 1_000,
+                PlayerDataKind::LoginCount => 5_000,
+                PlayerDataKind::AfkTicks => 20_000_000,
+            }
+        }
+
+        impl StubPlayerDataRepository {
+            #[must_use]
+            pub const fn new(player_count: u64) -> Self {
+                Self { player_count }
+            }
+
+            fn players(&self) -> impl Iterator<Item = (u64, Player)> {
+                // UUIDs of the form 00000000-0000-4000-8000-{index:012x}
+                const UUID_BASE: u128 = 0x4000_8000_0000_0000_0000;
+                const INDEX_MASK: u128 = 0xffff_ffff_ffff;
+
+                (0..self.player_count).map(|index| {
+                    let uuid = UUID_BASE | (u128::from(index) & INDEX_MASK);
+                    let player = Player::new(PlayerUuid::from_bytes(uuid.to_be_bytes()));
+
+                    (index, player)
+                })
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl PlayerDataRepository for StubPlayerDataRepository {
+            fn get_all_of(&self, kind: PlayerDataKind) -> PlayerDataStream<'_, (Player, u64)> {
+                let salt = kind.index() as u64 + 1;
+                let max = fake_value_max(kind);
+
+                stream::iter(
+                    self.players()
+                        .map(move |(index, player)| Ok((player, fake_value(index, salt, max)))),
+                )
+                .boxed()
+            }
+
+            fn get_all_last_seen(&self) -> PlayerDataStream<'_, (Player, SystemTime)> {
+                let now = SystemTime::now();
+
+                stream::iter(self.players().map(move |(index, player)| {
+                    let age = fake_value(index, 0, FAKE_LAST_SEEN_MAX_AGE.as_secs());
+                    Ok((player, now - Duration::from_secs(age)))
+                }))
+                .boxed()
+            }
+
+            fn get_all_names(&self) -> PlayerDataStream<'_, (Player, String)> {
+                stream::iter(
+                    self.players()
+                        .map(|(index, player)| Ok((player, format!("stub_player_{index}")))),
+                )
+                .boxed()
+            }
+
+            async fn check_readiness(&self) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            fn connection_state(&self) -> UpstreamConnectionState {
+                UpstreamConnectionState::Ready
+            }
+        }
+    }
+
+    pub mod file {
+        use crate::domain::{
+            GameServer, GroupMembershipRepository, Player, PlayerDataKind, PlayerDataRepository,
+            PlayerDataStream, PlayerUuid, UpstreamConnectionState,
+        };
+        use anyhow::{anyhow, Context};
+        use futures::stream::{self, StreamExt, TryStreamExt};
+        use std::collections::HashMap;
+        use std::path::{Path, PathBuf};
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        /// A row of a fixture file, with a column named after each kind, a `last_seen` column
+        /// holding a Unix time in seconds, a `name` column and a `server` column naming the game
+        /// server the row comes from. Values left out of a row are not reported for that player.
+        #[derive(Debug)]
+        struct FixtureRecord {
+            uuid: String,
+            server: Option<GameServer>,
+            values: [Option<u64>; PlayerDataKind::ALL.len()],
+            last_seen: Option<u64>,
+            name: Option<String>,
+        }
+
+        impl FixtureRecord {
+            /// Reads a row given as the text of each column, leaving out empty columns.
+            fn from_columns(columns: &HashMap<String, String>) -> anyhow::Result<Self> {
+                let uuid = columns
+                    .get("uuid")
+                    .ok_or_else(|| anyhow!("Missing uuid column"))?
+                    .clone();
+                let number_in = |column: &str| {
+                    columns
+                        .get(column)
+                        .filter(|value| !value.is_empty())
+                        .map(|value| {
+                            value
+                                .parse()
+                                .with_context(|| format!("Invalid {column} of {uuid}: {value}"))
+                        })
+                        .transpose()
+                };
+                let mut values = [None; PlayerDataKind::ALL.len()];
+                for kind in PlayerDataKind::ALL {
+                    values[kind.index()] = number_in(kind.as_str())?;
+                }
+                let last_seen = number_in("last_seen")?;
+                let server = columns
+                    .get("server")
+                    .filter(|server| !server.is_empty())
+                    .map(|server| GameServer::new(server))
+                    .transpose()?;
+                let name = columns.get("name").filter(|name| !name.is_empty()).cloned();
+
+                Ok(Self {
+                    uuid,
+                    server,
+                    values,
+                    last_seen,
+                    name,
+                })
+            }
+        }
+
+        /// Text of the columns of a JSON row, as if the row was read from a CSV file.
+        fn json_row_columns(row: HashMap<String, serde_json::Value>) -> HashMap<String, String> {
+            row.into_iter()
+                .filter_map(|(column, value)| match value {
+                    serde_json::Value::Null => None,
+                    serde_json::Value::String(value) => Some((column, value)),
+                    value => Some((column, value.to_string())),
+                })
+                .collect()
+        }
+
+        #[derive(Debug, Clone, Copy)]
+        enum FixtureFormat {
+            /// An array of objects with a field named after each column.
+            Json,
+            /// Records with a header naming each column.
+            Csv,
+        }
+
+        impl FixtureFormat {
+            fn of(path: &Path) -> anyhow::Result<Self> {
+                match path.extension().and_then(|extension| extension.to_str()) {
+                    Some("json") => Ok(Self::Json),
+                    Some("csv") => Ok(Self::Csv),
+                    _ => Err(anyhow!(
+                        "Cannot tell the format of {}, expected a .json or .csv file",
+                        path.display()
+                    )),
+                }
+            }
+
+            /// Reads the rows of a file as the text of each of their columns.
+            async fn read_rows(self, path: &Path) -> anyhow::Result<Vec<HashMap<String, String>>> {
+                let content = tokio::fs::read(path)
+                    .await
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+
+                match self {
+                    Self::Json => serde_json::from_slice(&content)
+                        .map(|rows: Vec<_>| rows.into_iter().map(json_row_columns).collect())
+                        .map_err(anyhow::Error::from),
+                    Self::Csv => csv::Reader::from_reader(content.as_slice())
+                        .deserialize()
+                        .collect::<Result<_, _>>()
+                        .map_err(anyhow::Error::from),
+                }
+                .with_context(|| format!("Failed to parse {}", path.display()))
+            }
+        }
+
+        /// Repository reading player data from a local JSON or CSV file, for example a snapshot
+        /// taken in production. The file is read on every fetch, so it can be replaced while the
+        /// translator is running.
+        #[derive(Debug, Clone)]
+        pub struct FilePlayerDataRepository {
+            path: PathBuf,
+            format: FixtureFormat,
+        }
+
+        impl FilePlayerDataRepository {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn new(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+                let path = path.into();
+                let format = FixtureFormat::of(&path)?;
+
+                Ok(Self { path, format })
+            }
+
+            async fn read_records(&self) -> anyhow::Result<Vec<FixtureRecord>> {
+                self.format
+                    .read_rows(&self.path)
+                    .await?
+                    .iter()
+                    .map(FixtureRecord::from_columns)
+                    .collect::<anyhow::Result<_>>()
+                    .with_context(|| format!("Failed to parse {}", self.path.display()))
+            }
+
+            /// Pairs each player with the value `value_of` finds in its row, skipping the rows
+            /// without one.
+            fn load<T: Send + 'static>(
+                &self,
+                value_of: impl Fn(&FixtureRecord) -> Option<T> + Clone + Send + 'static,
+            ) -> PlayerDataStream<'_, (Player, T)> {
+                stream::once(self.read_records())
+                    .map_ok(move |records| {
+                        let value_of = value_of.clone();
+                        stream::iter(records.into_iter().filter_map(move |record| {
+                            let value = value_of(&record)?;
+                            Some(PlayerUuid::parse(&record.uuid).map(|uuid| {
+                                let player = Player {
+                                    uuid,
+                                    server: record.server,
+                                };
+                                (player, value)
+                            }))
+                        }))
+                    })
+                    .try_flatten()
+                    .boxed()
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl PlayerDataRepository for FilePlayerDataRepository {
+            fn get_all_of(&self, kind: PlayerDataKind) -> PlayerDataStream<'_, (Player, u64)> {
+                self.load(move |record| record.values[kind.index()])
+            }
+
+            fn get_all_last_seen(&self) -> PlayerDataStream<'_, (Player, SystemTime)> {
+                self.load(|record| {
+                    record
+                        .last_seen
+                        .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds))
+                })
+            }
+
+            fn get_all_names(&self) -> PlayerDataStream<'_, (Player, String)> {
+                self.load(|record| record.name.clone())
+            }
+
+            async fn check_readiness(&self) -> anyhow::Result<()> {
+                tokio::fs::metadata(&self.path)
+                    .await
+                    .with_context(|| format!("Cannot access {}", self.path.display()))?;
+                Ok(())
+            }
+
+            fn connection_state(&self) -> UpstreamConnectionState {
+                UpstreamConnectionState::Ready
+            }
+        }
+
+        /// Group memberships read from a local JSON or CSV file with a `uuid` and a `group`
+        /// column, one row per membership. Like the player data, the file is read on every fetch.
+        #[derive(Debug, Clone)]
+        pub struct FileGroupMembershipRepository {
+            path: PathBuf,
+            format: FixtureFormat,
+        }
+
+        impl FileGroupMembershipRepository {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn new(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+                let path = path.into();
+                let format = FixtureFormat::of(&path)?;
+
+                Ok(Self { path, format })
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl GroupMembershipRepository for FileGroupMembershipRepository {
+            async fn get_all_memberships(&self) -> anyhow::Result<Vec<(PlayerUuid, String)>> {
+                self.format
+                    .read_rows(&self.path)
+                    .await?
+                    .iter()
+                    .map(|columns| {
+                        let column = |name: &str| {
+                            columns
+                                .get(name)
+                                .filter(|value| !value.is_empty())
+                                .ok_or_else(|| anyhow!("Missing {name} column"))
+                        };
+
+                        Ok((
+                            PlayerUuid::parse(column("uuid")?)?,
+                            column("group")?.clone(),
+                        ))
+                    })
+                    .collect::<anyhow::Result<_>>()
+                    .with_context(|| format!("Failed to parse {}", self.path.display()))
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::domain::PlayerDataKind;
+            use futures::TryStreamExt;
+
+            const PLAYER: &str = "00000000-0000-4000-8000-000000000001";
+
+            /// Writes `content` to a file of the temporary directory, named after `name` so that
+            /// its format is told by its extension.
+            fn fixture(name: &str, content: &str) -> PathBuf {
+                let path = std::env::temp_dir()
+                    .join(format!("translator-fixture-{}-{name}", std::process::id()));
+                std::fs::write(&path, content).unwrap();
+
+                path
+            }
+
+            async fn break_counts(path: PathBuf) -> Vec<(String, u64)> {
+                let repository = FilePlayerDataRepository::new(&path).unwrap();
+                let counts = repository
+                    .get_all_of(PlayerDataKind::BreakCount)
+                    .map_ok(|(player, break_count)| (player.uuid.to_string(), break_count))
+                    .try_collect()
+                    .await
+                    .unwrap();
+
+                std::fs::remove_file(path).unwrap();
+
+                counts
+            }
+
+            #[tokio::test]
+            async fn read_the_same_records_from_json_and_csv_files() {
+                let json = fixture(
+                    "players.json",
+                    r#"[
+                        {"uuid": "00000000-0000-4000-8000-000000000001", "break_count": 10},
+                        {"uuid": "00000000-0000-4000-8000-000000000002", "vote_count": 3}
+                    ]"#,
+                );
+                let csv = fixture(
+                    "players.csv",
+                    "uuid,break_count,build_count,play_ticks,vote_count\n\
+                     00000000-0000-4000-8000-000000000001,10,,,\n\
+                     00000000-0000-4000-8000-000000000002,,,,3\n",
+                );
+
+                // The second player has no break count, which is left out rather than zero
+                let expected = vec![(PLAYER.to_string(), 10)];
+                assert_eq!(break_counts(json).await, expected);
+                assert_eq!(break_counts(csv).await, expected);
+            }
+
+            #[test]
+            fn reject_files_of_other_formats() {
+                assert!(FilePlayerDataRepository::new("players.txt").is_err());
+            }
+        }
+    }
+
+    use buf_generated::gigantic_minecraft::seichi_game_data::v1::read_service_client::ReadServiceClient;
+    type GameDataGrpcClient = ReadServiceClient<
+        tonic::codegen::InterceptedService<tonic::transport::Channel, auth::BearerTokenInterceptor>,
+    >;
+
+    /// Capacity of the queue of endpoint changes sent to the balancer.
+    const ENDPOINT_CHANGE_BUFFER_SIZE: usize = 64;
+
+    #[derive(Debug)]
+    pub struct GameDataGrpcRepository {
+        endpoints: discovery::UpstreamEndpoints,
+        /// Clients on separate channels, so that concurrent requests are not limited by the
+        /// number of streams a single HTTP/2 connection allows.
+        clients: Vec<GameDataGrpcClient>,
+        next_client: AtomicUsize,
+        /// Senders of endpoint changes to the balancer of each channel in `clients`.
+        endpoint_changes: Vec<mpsc::Sender<Change<String, Endpoint>>>,
+        connection_state: AtomicU8,
+        retry_policy: retry::RetryPolicy,
+        rpc_timeout: Duration,
+        hedge_delay: Option<Duration>,
+        health_check_interval: Duration,
+        telemetry: telemetry::UpstreamTelemetry,
+    }
+
+    const fn encode_connection_state(state: UpstreamConnectionState) -> u8 {
+        match state {
+            UpstreamConnectionState::Connecting => 0,
+            UpstreamConnectionState::Ready => 1,
+            UpstreamConnectionState::Disconnected => 2,
+        }
+    }
+
+    const fn decode_connection_state(state: u8) -> UpstreamConnectionState {
+        match state {
+            0 => UpstreamConnectionState::Connecting,
+            1 => UpstreamConnectionState::Ready,
+            _ => UpstreamConnectionState::Disconnected,
+        }
+    }
+
+    impl GameDataGrpcRepository {
+        /// Sets up a client balancing requests over the upstream replicas without connecting to
+        /// them. Replicas are added to the balancer by the connection supervisor once they are
+        /// found to be reachable, so the translator can start before the game data server is
+        /// available.
+        #[tracing::instrument(skip(config, telemetry))]
+        pub fn initialize_connections_with(
+            config: config::GrpcClientConfig,
+            telemetry: telemetry::UpstreamTelemetry,
+        ) -> anyhow::Result<Self> {
+            let interceptor =
+                auth::BearerTokenInterceptor::new(config.upstream_auth_token.as_deref())?;
+
+            let send_compression = config.send_compression()?;
+            let accept_compression = config.accept_compression()?;
+
+            let (clients, endpoint_changes) = (0..config.upstream_channel_pool_size.max(1))
+                .map(|_| {
+                    let (channel, endpoint_changes) =
+                        Channel::balance_channel(ENDPOINT_CHANGE_BUFFER_SIZE);
+
+                    let mut client =
+                        ReadServiceClient::with_interceptor(channel, interceptor.clone())
+                            .max_decoding_message_size(config.upstream_max_decoding_message_size);
+                    if let Some(encoding) = send_compression {
+                        client = client.send_compressed(encoding);
+                    }
+                    if let Some(encoding) = accept_compression {
+                        client = client.accept_compressed(encoding);
+                    }
+
+                    (client, endpoint_changes)
+                })
+                .unzip();
+
+            Ok(Self {
+                endpoints: config.upstream_endpoints()?,
+                clients,
+                next_client: AtomicUsize::new(0),
+                endpoint_changes,
+                connection_state: AtomicU8::new(encode_connection_state(
+                    UpstreamConnectionState::Connecting,
+                )),
+                retry_policy: config.retry_policy(),
+                rpc_timeout: config.rpc_timeout(),
+                hedge_delay: config.hedge_delay(),
+                health_check_interval: config.health_check_interval(),
+                telemetry,
+            })
+        }
+
+        /// Picks a client from the pool in a round-robin fashion.
+        pub(crate) fn game_data_client(&self) -> GameDataGrpcClient {
+            let index = self.next_client.fetch_add(1, Ordering::Relaxed) % self.clients.len();
+            self.clients[index].clone()
+        }
+
+        async fn send_endpoint_change(
+            &self,
+            key: &str,
+            endpoint: Option<&Endpoint>,
+        ) -> anyhow::Result<()> {
+            for endpoint_changes in &self.endpoint_changes {
+                let change = endpoint.map_or_else(
+                    || Change::Remove(key.to_string()),
+                    |endpoint| Change::Insert(key.to_string(), endpoint.clone()),
+                );
+                endpoint_changes.send(change).await?;
+            }
+
+            Ok(())
+        }
+
+        /// Discovers the upstream replicas and returns the ones that accept connections.
+        async fn probe_endpoints(&self) -> anyhow::Result<HashMap<String, Endpoint>> {
+            let rpc_timeout = self.rpc_timeout;
+            let probes =
+                self.endpoints
+                    .discover()
+                    .await?
+                    .into_iter()
+                    .map(|(key, endpoint)| async move {
+                        let reachable = matches!(
+                            tokio::time::timeout(rpc_timeout, endpoint.connect()).await,
+                            Ok(Ok(_))
+                        );
+
+                        reachable.then_some((key, endpoint))
+                    });
+
+            Ok(futures::future::join_all(probes)
+                .await
+                .into_iter()
+                .flatten()
+                .collect())
+        }
+
+        /// Inserts newly healthy endpoints into the balancer and evicts the ones that are gone or
+        /// unreachable, returning the keys of the endpoints in the balancer afterwards.
+        async fn update_balancer(
+            &self,
+            active_endpoints: HashSet<String>,
+            healthy_endpoints: HashMap<String, Endpoint>,
+        ) -> anyhow::Result<HashSet<String>> {
+            for key in &active_endpoints {
+                if !healthy_endpoints.contains_key(key) {
+                    tracing::warn!("evicting unreachable upstream endpoint {}", key);
+                    self.send_endpoint_change(key, None).await?;
+                }
+            }
+
+            let healthy_keys = healthy_endpoints.keys().cloned().collect();
+
+            for (key, endpoint) in healthy_endpoints {
+                if !active_endpoints.contains(&key) {
+                    tracing::info!("adding upstream endpoint {}", key);
+                    self.send_endpoint_change(&key, Some(&endpoint)).await?;
+                }
+            }
+
+            Ok(healthy_keys)
+        }
+
+        /// Spawns a task that periodically discovers and probes the upstream replicas, keeping
+        /// only the reachable ones in the balancer and tracking the overall connection state.
+        /// Evicted replicas get a fresh connection once they are inserted again, so requests do
+        /// not keep using a broken connection.
+        pub fn spawn_connection_supervisor(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+            let repository = Arc::downgrade(self);
+
+            tokio::spawn(async move {
+                let mut active_endpoints = HashSet::new();
+
+                while let Some(repository) = repository.upgrade() {
+                    match repository.probe_endpoints().await {
+                        Ok(healthy_endpoints) => {
+                            match repository
+                                .update_balancer(active_endpoints.clone(), healthy_endpoints)
+                                .await
+                            {
+                                Ok(updated_endpoints) => active_endpoints = updated_endpoints,
+                                Err(e) => tracing::error!("failed to update balancer: {:?}", e),
+                            }
+
+                            let new_state = if active_endpoints.is_empty() {
+                                UpstreamConnectionState::Disconnected
+                            } else {
+                                UpstreamConnectionState::Ready
+                            };
+                            let previous_state = decode_connection_state(
+                                repository
+                                    .connection_state
+                                    .swap(encode_connection_state(new_state), Ordering::Relaxed),
+                            );
+
+                            if new_state != previous_state {
+                                tracing::info!(
+                                    "upstream connection state changed from {} to {}",
+                                    previous_state.as_str(),
+                                    new_state.as_str()
+                                );
+                            }
+                        }
+                        Err(e) => tracing::warn!("failed to discover upstream endpoints: {:?}", e),
+                    }
+
+                    let interval = repository.health_check_interval;
+                    drop(repository);
+                    tokio::time::sleep(interval).await;
+                }
+            })
+        }
+
+        /// Records of an RPC returning every record of a kind, whose response holds them in a
+        /// list taken out by `into_records`.
+        fn get_all_with<'a, T, R, F, Fut>(
+            &'a self,
+            rpc_name: &'static str,
+            call: F,
+            into_records: fn(T) -> Vec<R>,
+        ) -> PlayerDataStream<'a, (Player, R::Value)>
+        where
+            F: Fn(GameDataGrpcClient, tonic::Request<pbjson_types::Empty>) -> Fut
+                + Send
+                + Sync
+                + 'static,
+            Fut: Future<Output = Result<tonic::Response<T>, tonic::Status>> + Send,
+            T: prost::Message + 'a,
+            R: buf_generated_to_domain::GeneratedPlayerRecord + Send + 'static,
+            R::Value: Send + 'static,
+        {
+            stream::once(async move {
+                let response = self.call_with_retry(rpc_name, call).await?;
+
+                self.convert_records(
+                    rpc_name,
+                    into_records(response),
+                    buf_generated_to_domain::try_into_domain_record,
+                )
+                .await
+            })
+            .map_ok(|records| stream::iter(records.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
+        }
+
+        /// Converts the records of a response on the blocking pool, dropping the ones that cannot
+        /// be represented in the domain so that a single malformed record does not fail the whole
+        /// fetch.
+        async fn convert_records<R, T>(
+            &self,
+            rpc_name: &'static str,
+            records: Vec<R>,
+            convert: fn(R) -> anyhow::Result<T>,
+        ) -> anyhow::Result<Vec<T>>
+        where
+            R: Send + 'static,
+            T: Send + 'static,
+        {
+            let telemetry = self.telemetry.clone();
+
+            Ok(tokio::task::spawn_blocking(move || {
+                records
+                    .into_iter()
+                    .filter_map(|record| match convert(record) {
+                        Ok(record) => Some(record),
+                        Err(e) => {
+                            tracing::warn!("skipping invalid record from {}: {:?}", rpc_name, e);
+                            telemetry.record_invalid_record(rpc_name);
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .await?)
+        }
+
+        /// Invokes an RPC on a fresh client handle, retrying according to the configured policy.
+        /// Each attempt is bounded by the configured RPC timeout, and running out of time on the
+        /// last attempt is reported as [`UpstreamTimeoutError`].
+        #[tracing::instrument(skip(self, call))]
+        async fn call_with_retry<T, F, Fut>(
+            &self,
+            rpc_name: &'static str,
+            call: F,
+        ) -> anyhow::Result<T>
+        where
+            F: Fn(GameDataGrpcClient, tonic::Request<pbjson_types::Empty>) -> Fut + Send + Sync,
+            Fut: Future<Output = Result<tonic::Response<T>, tonic::Status>> + Send,
+            T: prost::Message,
+        {
+            let rpc_timeout = self.rpc_timeout;
+            let attempt = || {
+                let span =
+                    tracing::info_span!("upstream_rpc", rpc = rpc_name, otel.kind = "client");
+                let mut request = empty_request();
+                request.set_timeout(rpc_timeout);
+                infra_tracing::inject_trace_context(&span, request.metadata_mut());
+                let response = call(self.game_data_client(), request);
+
+                async move {
+                    tokio::time::timeout(rpc_timeout, response)
+                        .await
+                        .map_err(|_| {
+                            tonic::Status::deadline_exceeded(format!(
+                                "{rpc_name} did not complete within {rpc_timeout:?}"
+                            ))
+                        })?
+                }
+                .instrument(span)
+            };
+            let started_at = Instant::now();
+            let result = self
+                .retry_policy
+                .run(rpc_name, || {
+                    retry::hedged(rpc_name, self.hedge_delay, &attempt)
+                })
+                .await;
+            self.telemetry
+                .observe(rpc_name, started_at.elapsed(), &result);
+
+            match result {
+                Ok(response) => Ok(response.into_inner()),
+                Err(status) if status.code() == tonic::Code::DeadlineExceeded => {
+                    Err(anyhow::Error::new(UpstreamTimeoutError {
+                        operation: rpc_name,
+                    })
+                    .context(status))
+                }
+                Err(status) => Err(status.into()),
+            }
+        }
+    }
+
+    fn empty_request() -> tonic::Request<pbjson_types::Empty> {
+        tonic::Request::new(pbjson_types::Empty::default())
+    }
+
+    use crate::domain::UpstreamConnectionState;
+    use crate::domain::{
+        Player, PlayerDataKind, PlayerDataStream, PlayerUuid, UpstreamTimeoutError,
+    };
+    use crate::infra_tracing;
+    use futures::stream::{self, StreamExt, TryStreamExt};
+    use std::collections::{HashMap, HashSet};
+    use std::future::Future;
+    use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant, SystemTime};
+    use tokio::sync::mpsc;
+    use tonic::transport::{Channel, Endpoint};
+    use tower::discover::Change;
+    use tracing::Instrument;
+
+    // The game data protocol only offers unary RPCs returning every record at once, so each
+    // response is necessarily buffered as a whole before its records are streamed out. Once
+    // server-streaming variants are published, their messages should be forwarded as they arrive.
+    // There are no per-player RPCs either, so single players are looked up by scanning the records.
+    #[async_trait::async_trait]
+    impl crate::domain::PlayerDataRepository for GameDataGrpcRepository {
+        fn get_all_of(&self, kind: PlayerDataKind) -> PlayerDataStream<'_, (Player, u64)> {
+            match kind {
+                PlayerDataKind::BreakCount => self.get_all_with(
+                    "BreakCounts",
+                    |mut client, request| async move { client.break_counts(request).await },
+                    |response| response.results,
+                ),
+                PlayerDataKind::BuildCount => self.get_all_with(
+                    "BuildCounts",
+                    |mut client, request| async move { client.build_counts(request).await },
+                    |response| response.results,
+                ),
+                PlayerDataKind::PlayTicks => self.get_all_with(
+                    "PlayTicks",
+                    |mut client, request| async move { client.play_ticks(request).await },
+                    |response| response.results,
+                ),
+                PlayerDataKind::VoteCount => self.get_all_with(
+                    "VoteCounts",
+                    |mut client, request| async move { client.vote_counts(request).await },
+                    |response| response.results,
+                ),
+                PlayerDataKind::LoginCount => self.get_all_with(
+                    "LoginCounts",
+                    |mut client, request| async move { client.login_counts(request).await },
+                    |response| response.results,
+                ),
+                PlayerDataKind::AfkTicks => self.get_all_with(
+                    "AfkTicks",
+                    |mut client, request| async move { client.afk_ticks(request).await },
+                    |response| response.results,
+                ),
+            }
+        }
+
+        fn get_all_last_seen(&self) -> PlayerDataStream<'_, (Player, SystemTime)> {
+            self.get_all_with(
+                "LastQuits",
+                |mut client, request| async move { client.last_quits(request).await },
+                |response| response.results,
+            )
+        }
+
+        /// There is no RPC for names, but every record carries the name of its player, so they are
+        /// taken from the play ticks, which every player has.
+        fn get_all_play_ticks_with_names(
+            &self,
+        ) -> PlayerDataStream<'_, (Player, (u64, Option<String>))> {
+            self.get_all_with(
+                "PlayTicks",
+                |mut client, request| async move { client.play_ticks(request).await },
+                |response| {
+                    response
+                        .results
+                        .into_iter()
+                        .map(buf_generated_to_domain::NamedPlayTicks)
+                        .collect()
+                },
+            )
+        }
+
+        /// Names come along with the play ticks, which are scanned until the player is found.
+        async fn get_name_for(&self, uuid: &PlayerUuid) -> anyhow::Result<Option<String>> {
+            let mut records = self.get_all_play_ticks_with_names();
+
+            while let Some((player, (_, name))) = records.try_next().await? {
+                if player.uuid == *uuid {
+                    return Ok(name);
+                }
+            }
+
+            Ok(None)
+        }
+
+        /// Upstreams that predate seasons do not implement the RPC, which is taken to mean that
+        /// they count no seasons.
+        async fn get_current_season(&self) -> anyhow::Result<Option<String>> {
+            let response = self
+                .call_with_retry("CurrentSeason", |mut client, request| async move {
+                    client.current_season(request).await
+                })
+                .await;
+
+            match response {
+                Ok(response) => Ok(Some(response.season).filter(|season| !season.is_empty())),
+                Err(e)
+                    if e.downcast_ref::<tonic::Status>()
+                        .is_some_and(|status| status.code() == tonic::Code::Unimplemented) =>
+                {
+                    Ok(None)
+                }
+                Err(e) => Err(e),
+            }
+        }
+
+        async fn check_readiness(&self) -> anyhow::Result<()> {
+            match self.connection_state() {
+                UpstreamConnectionState::Ready => Ok(()),
+                state => Err(anyhow::anyhow!("upstream connection is {}", state.as_str())),
+            }
+        }
+
+        fn connection_state(&self) -> UpstreamConnectionState {
+            decode_connection_state(self.connection_state.load(Ordering::Relaxed))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl crate::domain::GroupMembershipRepository for GameDataGrpcRepository {
+        async fn get_all_memberships(&self) -> anyhow::Result<Vec<(PlayerUuid, String)>> {
+            self.get_all_with(
+                "GuildMemberships",
+                |mut client, request| async move { client.guild_memberships(request).await },
+                |response| response.results,
+            )
+            .map_ok(|(player, guild)| (player.uuid, guild))
+            .try_collect()
+            .await
+        }
+    }
+}
+
+pub mod infra_repository_decorators {
+    pub mod instrumented {
+        use crate::domain::{
+            Player, PlayerDataKind, PlayerDataRepository, PlayerDataStream, PlayerUuid,
+            UpstreamConnectionState,
+        };
+        use futures::stream::{self, StreamExt};
+        use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+        use std::future::Future;
+        use std::sync::Arc;
+        use std::time::{Instant, SystemTime};
+        use tracing::Instrument;
+
+        /// A [`PlayerDataRepository`] decorator recording a span, the latency and the failures of
+        /// every call to the wrapped repository.
+        #[derive(Debug)]
+        pub struct InstrumentedRepository {
+            inner: Arc<dyn PlayerDataRepository>,
+            repository_name: &'static str,
+            call_duration: HistogramVec,
+            call_errors: IntCounterVec,
+        }
+
+        impl InstrumentedRepository {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn register(
+                inner: Arc<dyn PlayerDataRepository>,
+                repository_name: &'static str,
+                registry: &Registry,
+            ) -> anyhow::Result<Self> {
+                let call_duration = HistogramVec::new(
+                    HistogramOpts::new(
+                        "seichi_translator_repository_call_duration_seconds",
+                        "Time taken by calls to the player data repository, until their records are drained",
+                    ),
+                    &["repository", "method", "kind"],
+                )?;
+                let call_errors = IntCounterVec::new(
+                    Opts::new(
+                        "seichi_translator_repository_call_errors_total",
+                        "Calls to the player data repository that failed",
+                    ),
+                    &["repository", "method", "kind"],
+                )?;
+
+                registry.register(Box::new(call_duration.clone()))?;
+                registry.register(Box::new(call_errors.clone()))?;
+
+                Ok(Self {
+                    inner,
+                    repository_name,
+                    call_duration,
+                    call_errors,
+                })
+            }
+
+            fn span(&self, method: &'static str, kind: Option<PlayerDataKind>) -> tracing::Span {
+                tracing::info_span!(
+                    "repository_call",
+                    repository = self.repository_name,
+                    method,
+                    kind = kind.map(PlayerDataKind::as_str)
+                )
+            }
+
+            /// Calls not concerning a single kind are labelled with an empty kind.
+            fn observe(
+                &self,
+                method: &'static str,
+                kind: Option<PlayerDataKind>,
+                started_at: Instant,
+                failed: bool,
+            ) {
+                let labels = [
+                    self.repository_name,
+                    method,
+                    kind.map_or("", PlayerDataKind::as_str),
+                ];
+                self.call_duration
+                    .with_label_values(&labels)
+                    .observe(started_at.elapsed().as_secs_f64());
+                if failed {
+                    self.call_errors.with_label_values(&labels).inc();
+                }
+            }
+
+            async fn instrumented<T>(
+                &self,
+                method: &'static str,
+                kind: Option<PlayerDataKind>,
+                call: impl Future<Output = anyhow::Result<T>> + Send,
+            ) -> anyhow::Result<T> {
+                let started_at = Instant::now();
+                let result = call.instrument(self.span(method, kind)).await;
+                self.observe(method, kind, started_at, result.is_err());
+                result
+            }
+
+            fn instrumented_stream<'a, T: Send + 'a>(
+                &'a self,
+                method: &'static str,
+                kind: Option<PlayerDataKind>,
+                records: PlayerDataStream<'a, T>,
+            ) -> PlayerDataStream<'a, T> {
+                let span = self.span(method, kind);
+                let started_at = Instant::now();
+
+                stream::unfold(Some(records), move |records| {
+                    let span = span.clone();
+                    async move {
+                        let mut records = records?;
+                        match records.next().instrument(span).await {
+                            Some(Ok(record)) => Some((Ok(record), Some(records))),
+                            Some(Err(e)) => {
+                                self.observe(method, kind, started_at, true);
+                                Some((Err(e), None))
+                            }
+                            None => {
+                                self.observe(method, kind, started_at, false);
+                                None
+                            }
+                        }
+                    }
+                })
+                .boxed()
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl PlayerDataRepository for InstrumentedRepository {
+            fn get_all_of(&self, kind: PlayerDataKind) -> PlayerDataStream<'_, (Player, u64)> {
+                self.instrumented_stream("get_all_of", Some(kind), self.inner.get_all_of(kind))
+            }
+
+            fn get_all_last_seen(&self) -> PlayerDataStream<'_, (Player, SystemTime)> {
+                self.instrumented_stream("get_all_last_seen", None, self.inner.get_all_last_seen())
+            }
+
+            fn get_all_names(&self) -> PlayerDataStream<'_, (Player, String)> {
+                self.instrumented_stream("get_all_names", None, self.inner.get_all_names())
+            }
+
+            fn get_all_play_ticks_with_names(
+                &self,
+            ) -> PlayerDataStream<'_, (Player, (u64, Option<String>))> {
+                self.instrumented_stream(
+                    "get_all_play_ticks_with_names",
+                    Some(PlayerDataKind::PlayTicks),
+                    self.inner.get_all_play_ticks_with_names(),
+                )
+            }
+
+            async fn get_count_for(
+                &self,
+                uuid: &PlayerUuid,
+                kind: PlayerDataKind,
+            ) -> anyhow::Result<Option<u64>> {
+                self.instrumented(
+                    "get_count_for",
+                    Some(kind),
+                    self.inner.get_count_for(uuid, kind),
+                )
+                .await
+            }
+
+            async fn get_last_seen_for(
+                &self,
+                uuid: &PlayerUuid,
+            ) -> anyhow::Result<Option<SystemTime>> {
+                self.instrumented(
+                    "get_last_seen_for",
+                    None,
+                    self.inner.get_last_seen_for(uuid),
+                )
+                .await
+            }
+
+            async fn get_name_for(&self, uuid: &PlayerUuid) -> anyhow::Result<Option<String>> {
+                self.instrumented("get_name_for", None, self.inner.get_name_for(uuid))
+                    .await
+            }
+
+            async fn get_current_season(&self) -> anyhow::Result<Option<String>> {
+                self.instrumented("get_current_season", None, self.inner.get_current_season())
+                    .await
+            }
+
+            async fn check_readiness(&self) -> anyhow::Result<()> {
+                self.instrumented("check_readiness", None, self.inner.check_readiness())
+                    .await
+            }
+
+            fn connection_state(&self) -> UpstreamConnectionState {
+                self.inner.connection_state()
+            }
+        }
+    }
+
+    pub mod circuit_breaker {
+        use crate::domain::{
+            Player, PlayerDataKind, PlayerDataRepository, PlayerDataStream,
+            UpstreamConnectionState, UpstreamUnavailableError,
+        };
+        use crate::use_cases::HealthTracker;
+        use futures::stream::{self, StreamExt};
+        use std::sync::{Arc, Mutex, PoisonError};
+        use std::time::{Duration, Instant, SystemTime};
+
+        pub mod config {
+            use crate::config::{ConfigSources, Settings};
+
+            const fn default_failure_threshold() -> u32 {
+                5
+            }
+
+            const fn default_open_duration_millis() -> u64 {
+                30_000
+            }
+
+            const fn default_serve_cached() -> bool {
+                true
+            }
+
+            /// Read from `CIRCUIT_BREAKER_`-prefixed environment variables.
+            #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+            pub struct CircuitBreakerConfig {
+                /// Number of consecutive failures that opens the circuit. `0` disables the breaker.
+                #[serde(default = "default_failure_threshold")]
+                pub failure_threshold: u32,
+                /// How long the circuit stays open before a probe request is let through.
+                #[serde(default = "default_open_duration_millis")]
+                pub open_duration_millis: u64,
+                /// Whether to serve the last successful result instead of failing fast while open.
+                #[serde(default = "default_serve_cached")]
+                pub serve_cached: bool,
+            }
+
+            impl Settings for CircuitBreakerConfig {}
+
+            impl CircuitBreakerConfig {
+                #[allow(clippy::missing_errors_doc)]
+                pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                    sources.extract_prefixed("CIRCUIT_BREAKER_")
+                }
+
+                #[must_use]
+                pub const fn is_enabled(&self) -> bool {
+                    self.failure_threshold > 0
+                }
+            }
+        }
+
+        #[derive(Debug, Clone, Copy)]
+        enum CircuitState {
+            Closed {
+                consecutive_failures: u32,
+            },
+            Open {
+                until: Instant,
+            },
+            /// A single probe request has been let through and its outcome decides the next state.
+            HalfOpen,
+        }
+
+        /// Records of the last successful fetch, kept to be served while the circuit is open.
+        type LastResult<T> = Mutex<Option<Vec<T>>>;
+
+        /// A [`PlayerDataRepository`] decorator that stops calling the wrapped repository after
+        /// it has failed a number of times in a row, giving a struggling upstream time to recover.
+        #[derive(Debug)]
+        pub struct CircuitBreakingRepository {
+            inner: Arc<dyn PlayerDataRepository>,
+            failure_threshold: u32,
+            open_duration: Duration,
+            serve_cached: bool,
+            state: Mutex<CircuitState>,
+            /// Last results of each kind, indexed by [`PlayerDataKind::index`].
+            last_results: [LastResult<(Player, u64)>; PlayerDataKind::ALL.len()],
+            last_seen_result: LastResult<(Player, SystemTime)>,
+            names_result: LastResult<(Player, String)>,
+            named_play_ticks_result: LastResult<(Player, (u64, Option<String>))>,
+            last_season: Mutex<Option<String>>,
+            health: Arc<HealthTracker>,
+        }
+
+        impl CircuitBreakingRepository {
+            pub fn new(
+                inner: Arc<dyn PlayerDataRepository>,
+                config: &config::CircuitBreakerConfig,
+                health: Arc<HealthTracker>,
+            ) -> Self {
+                Self {
+                    inner,
+                    failure_threshold: config.failure_threshold,
+                    open_duration: Duration::from_millis(config.open_duration_millis),
+                    serve_cached: config.serve_cached,
+                    state: Mutex::new(CircuitState::Closed {
+                        consecutive_failures: 0,
+                    }),
+                    last_results: Default::default(),
+                    last_seen_result: Mutex::default(),
+                    names_result: Mutex::default(),
+                    named_play_ticks_result: Mutex::default(),
+                    last_season: Mutex::default(),
+                    health,
+                }
+            }
+
+            /// Decides whether a request may be sent to the wrapped repository.
+            fn try_acquire_permission(&self) -> bool {
+                let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+                match *state {
+                    CircuitState::Closed { .. } => true,
+                    CircuitState::Open { until } if Instant::now() >= until => {
+                        tracing::info!("circuit half-opened, sending a probe request upstream");
+                        *state = CircuitState::HalfOpen;
+                        true
+                    }
+                    CircuitState::Open { .. } | CircuitState::HalfOpen => false,
+                }
+            }
+
+            fn is_closed(&self) -> bool {
+                matches!(
+                    *self.state.lock().unwrap_or_else(PoisonError::into_inner),
+                    CircuitState::Closed { .. }
+                )
+            }
+
+            fn record_success(&self) {
+                let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+                if matches!(*state, CircuitState::HalfOpen) {
+                    tracing::info!("probe request succeeded, closing circuit");
+                }
+                *state = CircuitState::Closed {
+                    consecutive_failures: 0,
+                };
+            }
+
+            fn record_failure(&self) {
+                let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+                let should_open = match *state {
+                    CircuitState::Closed {
+                        consecutive_failures,
+                    } => {
+                        let consecutive_failures = consecutive_failures + 1;
+                        *state = CircuitState::Closed {
+                            consecutive_failures,
+                        };
+                        consecutive_failures >= self.failure_threshold
+                    }
+                    CircuitState::HalfOpen => true,
+                    CircuitState::Open { .. } => false,
+                };
+
+                if should_open {
+                    tracing::warn!("opening circuit for {:?}", self.open_duration);
+                    *state = CircuitState::Open {
+                        until: Instant::now() + self.open_duration,
+                    };
+                }
+            }
+
+            /// A fetch that was abandoned before completing says nothing about the upstream, but
+            /// it must not leave the circuit half-open forever, so the next request probes again.
+            fn record_cancellation(&self) {
+                let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+                if matches!(*state, CircuitState::HalfOpen) {
+                    *state = CircuitState::Open {
+                        until: Instant::now(),
+                    };
+                }
+            }
+
+            fn guarded<'a, T>(
+                &'a self,
+                last_result: &'a LastResult<T>,
+                fetch: impl FnOnce() -> PlayerDataStream<'a, T>,
+            ) -> PlayerDataStream<'a, T>
+            where
+                T: Clone + Send + 'a,
+            {
+                if !self.try_acquire_permission() {
+                    let cached = last_result
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .clone();
+
+                    return match cached {
+                        Some(cached) if self.serve_cached => {
+                            self.health.record_cached_results_served();
+                            stream::iter(cached.into_iter().map(Ok)).boxed()
+                        }
+                        _ => stream::iter([Err(UpstreamUnavailableError {
+                            reason: "circuit breaker is open".to_string(),
+                        }
+                        .into())])
+                        .boxed(),
+                    };
+                }
+
+                let fetch = GuardedFetch {
+                    repository: self,
+                    records: fetch(),
+                    collected: self.serve_cached.then(Vec::new),
+                    finished: false,
+                };
+
+                // The outcome of the fetch is only known once its stream has been drained
+                stream::unfold(fetch, move |mut fetch| async move {
+                    if fetch.finished {
+                        return None;
+                    }
+
+                    match fetch.records.next().await {
+                        Some(Ok(record)) => {
+                            if let Some(collected) = &mut fetch.collected {
+                                collected.push(record.clone());
+                            }
+                            Some((Ok(record), fetch))
+                        }
+                        Some(Err(e)) => {
+                            fetch.finished = true;
+                            fetch.repository.record_failure();
+                            Some((Err(e), fetch))
+                        }
+                        None => {
+                            fetch.finished = true;
+                            fetch.repository.record_success();
+                            if let Some(collected) = fetch.collected.take() {
+                                *last_result.lock().unwrap_or_else(PoisonError::into_inner) =
+                                    Some(collected);
+                            }
+                            None
+                        }
+                    }
+                })
+                .boxed()
+            }
+        }
+
+        /// A fetch let through the breaker, whose outcome is recorded once its stream ends.
+        struct GuardedFetch<'a, T> {
+            repository: &'a CircuitBreakingRepository,
+            records: PlayerDataStream<'a, T>,
+            /// Records received so far, kept only if they are to be served while the circuit is open.
+            collected: Option<Vec<T>>,
+            finished: bool,
+        }
+
+        impl<T> Drop for GuardedFetch<'_, T> {
+            fn drop(&mut self) {
+                if !self.finished {
+                    self.repository.record_cancellation();
+                }
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl PlayerDataRepository for CircuitBreakingRepository {
+            fn get_all_of(&self, kind: PlayerDataKind) -> PlayerDataStream<'_, (Player, u64)> {
+                self.guarded(&self.last_results[kind.index()], || {
+                    self.inner.get_all_of(kind)
+                })
+            }
+
+            fn get_all_last_seen(&self) -> PlayerDataStream<'_, (Player, SystemTime)> {
+                self.guarded(&self.last_seen_result, || self.inner.get_all_last_seen())
+            }
+
+            fn get_all_names(&self) -> PlayerDataStream<'_, (Player, String)> {
+                self.guarded(&self.names_result, || self.inner.get_all_names())
+            }
+
+            fn get_all_play_ticks_with_names(
+                &self,
+            ) -> PlayerDataStream<'_, (Player, (u64, Option<String>))> {
+                self.guarded(&self.named_play_ticks_result, || {
+                    self.inner.get_all_play_ticks_with_names()
+                })
+            }
+
+            /// The season says little about the health of the upstream, so fetching it is left out
+            /// of the failure count. It is still not fetched while the circuit is not closed, and
+            /// the last season fetched is reported instead.
+            async fn get_current_season(&self) -> anyhow::Result<Option<String>> {
+                if !self.is_closed() {
+                    return Ok(self
+                        .last_season
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .clone());
+                }
+
+                let season = self.inner.get_current_season().await?;
+                self.last_season
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone_from(&season);
+                Ok(season)
+            }
+
+            async fn check_readiness(&self) -> anyhow::Result<()> {
+                self.inner.check_readiness().await
+            }
+
+            fn connection_state(&self) -> UpstreamConnectionState {
+                self.inner.connection_state()
+            }
+        }
+    }
+
+    pub mod multi_server {
+        use crate::domain::{
+            GameServer, Player, PlayerDataKind, PlayerDataRepository, PlayerDataStream, PlayerUuid,
+            UpstreamConnectionState,
+        };
+        use anyhow::Context;
+        use futures::future;
+        use futures::stream::{self, StreamExt, TryStreamExt};
+        use std::sync::Arc;
+        use std::time::SystemTime;
+
+        /// A [`PlayerDataRepository`] fetching from several game servers at once and tagging
+        /// every record with the server it comes from.
+        ///
+        /// A failure of any server fails the whole fetch, as statistics missing a server would
+        /// look like they dropped. Single players are looked up on every server and their values
+        /// summed, like the aggregation does unless the servers are kept apart.
+        #[derive(Debug)]
+        pub struct MultiServerRepository {
+            servers: Vec<(GameServer, Arc<dyn PlayerDataRepository>)>,
+        }
+
+        impl MultiServerRepository {
+            #[must_use]
+            pub fn new(servers: Vec<(GameServer, Arc<dyn PlayerDataRepository>)>) -> Self {
+                Self { servers }
+            }
+
+            fn tagged<'a, T: Send + 'a>(
+                &'a self,
+                records_of: impl Fn(&'a dyn PlayerDataRepository) -> PlayerDataStream<'a, (Player, T)>,
+            ) -> PlayerDataStream<'a, (Player, T)> {
+                stream::select_all(self.servers.iter().map(|(server, repository)| {
+                    let server = *server;
+                    records_of(repository.as_ref())
+                        .map_ok(move |(player, value)| (player.on(server), value))
+                }))
+                .boxed()
+            }
+
+            /// Result of `call` on every server, with failures naming the server.
+            async fn on_every_server<'a, T, Fut>(
+                &'a self,
+                call: impl Fn(&'a dyn PlayerDataRepository) -> Fut,
+            ) -> anyhow::Result<Vec<T>>
+            where
+                Fut: future::Future<Output = anyhow::Result<T>> + 'a,
+            {
+                future::try_join_all(self.servers.iter().map(|(server, repository)| {
+                    let response = call(repository.as_ref());
+                    async move {
+                        response
+                            .await
+                            .with_context(|| format!("game server {}", server.as_str()))
+                    }
+                }))
+                .await
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl PlayerDataRepository for MultiServerRepository {
+            fn get_all_of(&self, kind: PlayerDataKind) -> PlayerDataStream<'_, (Player, u64)> {
+                self.tagged(|repository| repository.get_all_of(kind))
+            }
+
+            fn get_all_last_seen(&self) -> PlayerDataStream<'_, (Player, SystemTime)> {
+                self.tagged(|repository| repository.get_all_last_seen())
+            }
+
+            fn get_all_names(&self) -> PlayerDataStream<'_, (Player, String)> {
+                self.tagged(|repository| repository.get_all_names())
+            }
+
+            fn get_all_play_ticks_with_names(
+                &self,
+            ) -> PlayerDataStream<'_, (Player, (u64, Option<String>))> {
+                self.tagged(|repository| repository.get_all_play_ticks_with_names())
+            }
+
+            async fn get_count_for(
+                &self,
+                uuid: &PlayerUuid,
+                kind: PlayerDataKind,
+            ) -> anyhow::Result<Option<u64>> {
+                let counts = self
+                    .on_every_server(|repository| repository.get_count_for(uuid, kind))
+                    .await?;
+
+                Ok(counts.into_iter().flatten().reduce(u64::saturating_add))
+            }
+
+            async fn get_last_seen_for(
+                &self,
+                uuid: &PlayerUuid,
+            ) -> anyhow::Result<Option<SystemTime>> {
+                let last_seen = self
+                    .on_every_server(|repository| repository.get_last_seen_for(uuid))
+                    .await?;
+
+                Ok(last_seen.into_iter().flatten().max())
+            }
+
+            /// The name known to the first server that knows the player.
+            async fn get_name_for(&self, uuid: &PlayerUuid) -> anyhow::Result<Option<String>> {
+                let names = self
+                    .on_every_server(|repository| repository.get_name_for(uuid))
+                    .await?;
+
+                Ok(names.into_iter().flatten().next())
+            }
+
+            /// The season of the first server that reports one, as the servers are expected to
+            /// share their seasons.
+            async fn get_current_season(&self) -> anyhow::Result<Option<String>> {
+                let seasons = self
+                    .on_every_server(PlayerDataRepository::get_current_season)
+                    .await?;
+
+                Ok(seasons.into_iter().flatten().next())
+            }
+
+            async fn check_readiness(&self) -> anyhow::Result<()> {
+                self.on_every_server(PlayerDataRepository::check_readiness)
+                    .await?;
+                Ok(())
+            }
+
+            /// The worst state among the servers.
+            fn connection_state(&self) -> UpstreamConnectionState {
+                let states = self
+                    .servers
+                    .iter()
+                    .map(|(_, repository)| repository.connection_state())
+                    .collect::<Vec<_>>();
+
+                [
+                    UpstreamConnectionState::Disconnected,
+                    UpstreamConnectionState::Connecting,
+                ]
+                .into_iter()
+                .find(|state| states.contains(state))
+                .unwrap_or(UpstreamConnectionState::Ready)
+            }
+        }
+    }
+}
+
+pub mod infra_snapshot_persistence {
+    use crate::domain::{
+        FetchedPlayerData, KnownAggregatedPlayerData, PlayerDataKind, SnapshotPersistence,
+    };
+    use anyhow::Context;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    pub mod config {
+        use crate::config::{ConfigSources, Settings};
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        pub struct SnapshotPersistenceConfig {
+            /// File the latest complete snapshot is saved to and restored from on startup.
+            pub snapshot_persistence_path: Option<String>,
+        }
+
+        impl Settings for SnapshotPersistenceConfig {}
+
+        impl SnapshotPersistenceConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                sources.extract()
+            }
+        }
+    }
+
+    /// Compression level of saved snapshots, favouring speed as snapshots are saved often.
+    const ZSTD_LEVEL: i32 = 3;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    struct PersistedSnapshot<D> {
+        /// Kinds known when the snapshot was taken, so that the kinds added since are restored
+        /// as missing. The data keeps the kinds by name, so kinds unknown to the reader are
+        /// dropped.
+        kinds: Vec<String>,
+        /// The data of the players, borrowed while saving.
+        data: D,
+        missing_kinds: Vec<String>,
+        disabled_kinds: Vec<String>,
+        season: Option<String>,
+    }
+
+    fn kind_names(kinds: &[PlayerDataKind]) -> Vec<String> {
+        kinds.iter().map(|kind| kind.as_str().to_string()).collect()
+    }
+
+    fn parse_kinds(names: &[String]) -> Vec<PlayerDataKind> {
+        names
+            .iter()
+            .filter_map(|name| PlayerDataKind::from_name(name))
+            .collect()
+    }
+
+    /// Saves snapshots as zstd-compressed bincode to a local file.
+    #[derive(Debug, Clone)]
+    pub struct DiskSnapshotPersistence {
+        path: PathBuf,
+    }
+
+    impl DiskSnapshotPersistence {
+        pub fn new(path: impl Into<PathBuf>) -> Self {
+            Self { path: path.into() }
+        }
+    }
+
+    impl SnapshotPersistence for DiskSnapshotPersistence {
+        #[tracing::instrument(skip_all)]
+        fn save(&self, fetched: &FetchedPlayerData) -> anyhow::Result<()> {
+            let snapshot = PersistedSnapshot {
+                kinds: kind_names(&PlayerDataKind::ALL),
+                data: &fetched.data,
+                missing_kinds: kind_names(&fetched.missing_kinds),
+                disabled_kinds: kind_names(&fetched.disabled_kinds),
+                season: fetched.season.clone(),
+            };
+
+            let encoded = zstd::encode_all(bincode::serialize(&snapshot)?.as_slice(), ZSTD_LEVEL)?;
+
+            // Written aside and renamed so that a crash never leaves a truncated snapshot behind
+            let temporary_path = self.path.with_extension("tmp");
+            std::fs::write(&temporary_path, encoded)
+                .with_context(|| format!("Failed to write {}", temporary_path.display()))?;
+            std::fs::rename(&temporary_path, &self.path)
+                .with_context(|| format!("Failed to replace {}", self.path.display()))?;
+
+            Ok(())
+        }
+
+        #[tracing::instrument(skip_all)]
+        fn load(&self) -> anyhow::Result<Option<FetchedPlayerData>> {
+            let encoded = match std::fs::read(&self.path) {
+                Ok(encoded) => encoded,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => {
+                    return Err(anyhow::Error::new(e)
+                        .context(format!("Failed to read {}", self.path.display())))
+                }
+            };
+            let decoded = zstd::decode_all(encoded.as_slice())
+                .map_err(anyhow::Error::from)
+                .and_then(|decoded| {
+                    Ok(bincode::deserialize::<
+                        PersistedSnapshot<KnownAggregatedPlayerData>,
+                    >(&decoded)?)
+                });
+            // A snapshot is only a head start, so one left by a version that saved them in
+            // another format is not worth failing the startup for
+            let snapshot = match decoded {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    tracing::warn!(
+                        "ignoring undecodable snapshot {}: {e:?}",
+                        self.path.display()
+                    );
+                    return Ok(None);
+                }
+            };
+
+            let kinds = parse_kinds(&snapshot.kinds);
+            let missing_kinds = parse_kinds(&snapshot.missing_kinds);
+            let unsaved_kinds = PlayerDataKind::ALL
+                .into_iter()
+                .filter(|kind| !kinds.contains(kind) && !missing_kinds.contains(kind))
+                .collect::<Vec<_>>();
+
+            Ok(Some(FetchedPlayerData {
+                data: snapshot.data,
+                missing_kinds: [missing_kinds, unsaved_kinds].concat(),
+                disabled_kinds: parse_kinds(&snapshot.disabled_kinds),
+                duplicate_records: HashMap::new(),
+                fetch_durations: HashMap::new(),
+                season: snapshot.season,
+            }))
+        }
+    }
+}
+
+pub mod infra_runtime_telemetry {
+    use prometheus::core::{Collector, Desc};
+    use prometheus::proto::MetricFamily;
+    use prometheus::{Counter, Gauge, IntCounter, IntGauge, Opts};
+    use std::sync::{Mutex, PoisonError};
+    use std::time::Duration;
+    use tokio::runtime::Handle;
+
+    fn to_i64(value: usize) -> i64 {
+        i64::try_from(value).unwrap_or(i64::MAX)
+    }
+
+    /// Reads the metrics of the Tokio runtime whenever they are gathered.
+    #[derive(Debug)]
+    pub struct TokioRuntimeCollector {
+        handle: Handle,
+        workers: IntGauge,
+        alive_tasks: IntGauge,
+        blocking_threads: IntGauge,
+        idle_blocking_threads: IntGauge,
+        blocking_queue_depth: IntGauge,
+        injection_queue_depth: IntGauge,
+        polls: IntCounter,
+        busy_duration: Counter,
+        mean_poll_duration: Gauge,
+        // Counters are reset and refilled while collecting, which must not interleave
+        collecting: Mutex<()>,
+    }
+
+    impl TokioRuntimeCollector {
+        /// Registers a collector of the runtime this is called on.
+        #[allow(clippy::missing_errors_doc)]
+        pub fn register(registry: &prometheus::Registry) -> anyhow::Result<()> {
+            let gauge = |name: &str, help: &str| IntGauge::with_opts(Opts::new(name, help));
+
+            let collector = Self {
+                handle: Handle::current(),
+                workers: gauge(
+                    "seichi_translator_tokio_workers",
+                    "Worker threads of the runtime",
+                )?,
+                alive_tasks: gauge(
+                    "seichi_translator_tokio_alive_tasks",
+                    "Tasks that have been spawned and have not completed yet",
+                )?,
+                blocking_threads: gauge(
+                    "seichi_translator_tokio_blocking_threads",
+                    "Threads of the blocking pool",
+                )?,
+                idle_blocking_threads: gauge(
+                    "seichi_translator_tokio_idle_blocking_threads",
+                    "Threads of the blocking pool that are idle",
+                )?,
+                blocking_queue_depth: gauge(
+                    "seichi_translator_tokio_blocking_queue_depth",
+                    "Blocking tasks waiting for a thread of the blocking pool",
+                )?,
+                injection_queue_depth: gauge(
+                    "seichi_translator_tokio_injection_queue_depth",
+                    "Tasks waiting in the global queue for a worker",
+                )?,
+                polls: IntCounter::with_opts(Opts::new(
+                    "seichi_translator_tokio_polls_total",
+                    "Tasks polled by the workers",
+                ))?,
+                busy_duration: Counter::with_opts(Opts::new(
+                    "seichi_translator_tokio_busy_seconds_total",
+                    "Time the workers spent polling tasks",
+                ))?,
+                mean_poll_duration: Gauge::with_opts(Opts::new(
+                    "seichi_translator_tokio_mean_poll_duration_seconds",
+                    "Recent average time taken to poll a task, averaged over the workers",
+                ))?,
+                collecting: Mutex::default(),
+            };
+            registry.register(Box::new(collector))?;
+
+            Ok(())
+        }
+    }
+
+    impl Collector for TokioRuntimeCollector {
+        fn desc(&self) -> Vec<&Desc> {
+            [
+                self.workers.desc(),
+                self.alive_tasks.desc(),
+                self.blocking_threads.desc(),
+                self.idle_blocking_threads.desc(),
+                self.blocking_queue_depth.desc(),
+                self.injection_queue_depth.desc(),
+                self.polls.desc(),
+                self.busy_duration.desc(),
+                self.mean_poll_duration.desc(),
+            ]
+            .concat()
+        }
+
+        fn collect(&self) -> Vec<MetricFamily> {
+            let _collecting = self
+                .collecting
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let metrics = self.handle.metrics();
+            let workers = metrics.num_workers();
+
+            self.workers.set(to_i64(workers));
+            self.alive_tasks.set(to_i64(metrics.num_alive_tasks()));
+            self.blocking_threads
+                .set(to_i64(metrics.num_blocking_threads()));
+            self.idle_blocking_threads
+                .set(to_i64(metrics.num_idle_blocking_threads()));
+            self.blocking_queue_depth
+                .set(to_i64(metrics.blocking_queue_depth()));
+            self.injection_queue_depth
+                .set(to_i64(metrics.injection_queue_depth()));
+
+            self.polls.reset();
+            self.polls.inc_by(
+                (0..workers)
+                    .map(|worker| metrics.worker_poll_count(worker))
+                    .sum(),
+            );
+            self.busy_duration.reset();
+            self.busy_duration.inc_by(
+                (0..workers)
+                    .map(|worker| metrics.worker_total_busy_duration(worker))
+                    .sum::<Duration>()
+                    .as_secs_f64(),
+            );
+            let mean_poll_durations = (0..workers)
+                .map(|worker| metrics.worker_mean_poll_time(worker).as_secs_f64())
+                .sum::<f64>();
+            #[allow(clippy::cast_precision_loss)]
+            self.mean_poll_duration
+                .set(mean_poll_durations / workers.max(1) as f64);
+
+            [
+                self.workers.collect(),
+                self.alive_tasks.collect(),
+                self.blocking_threads.collect(),
+                self.idle_blocking_threads.collect(),
+                self.blocking_queue_depth.collect(),
+                self.injection_queue_depth.collect(),
+                self.polls.collect(),
+                self.busy_duration.collect(),
+                self.mean_poll_duration.collect(),
+            ]
+            .concat()
+        }
+    }
+}
+
+pub mod infra_systemd {
+    use sd_notify::NotifyState;
+    use std::future::Future;
+    use std::time::Duration;
+
+    fn notify(state: NotifyState) {
+        if let Err(e) = sd_notify::notify(false, &[state]) {
+            tracing::warn!("failed to notify systemd: {:?}", e);
+        }
+    }
+
+    /// Tells systemd that the service has started. Does nothing unless run as a systemd service
+    /// of `Type=notify`.
+    pub fn notify_ready() {
+        notify(NotifyState::Ready);
+    }
+
+    /// Interval at which the watchdog is to be petted, half of the `WatchdogSec` of the service,
+    /// or `None` if the watchdog is not enabled.
+    #[must_use]
+    pub fn watchdog_interval() -> Option<Duration> {
+        let mut timeout_usec = 0;
+
+        sd_notify::watchdog_enabled(false, &mut timeout_usec)
+            .then(|| Duration::from_micros(timeout_usec) / 2)
+    }
+
+    /// Tells systemd that the service is still alive, so that it is not restarted.
+    pub fn pet_watchdog() {
+        if watchdog_interval().is_some() {
+            notify(NotifyState::Watchdog);
+        }
+    }
+
+    /// Awaits `future`, petting the watchdog while it is pending.
+    pub async fn while_petting_watchdog<F: Future>(future: F) -> F::Output {
+        let Some(interval) = watchdog_interval() else {
+            return future.await;
+        };
+        tokio::pin!(future);
+
+        loop {
+            tokio::select! {
+                output = &mut future => return output,
+                () = tokio::time::sleep(interval) => pet_watchdog(),
+            }
+        }
+    }
+}
+
+pub mod infra_tracing {
+    use axum::http::{HeaderMap, HeaderName, Request};
+    use opentelemetry::propagation::{Extractor, Injector};
+    use opentelemetry::sdk::propagation::TraceContextPropagator;
+    use opentelemetry::sdk::trace::{self, Sampler};
+    use opentelemetry::sdk::Resource;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use tonic::metadata::MetadataKey;
+    use tracing::Subscriber;
+    use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+    use tracing_subscriber::fmt::MakeWriter;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::{reload, EnvFilter, Layer};
+
+    pub mod config {
+        use crate::config::{ConfigSources, Settings};
+
+        fn default_otel_service_name() -> String {
+            "seichi-timed-stats-translator".to_string()
+        }
+
+        const fn default_otel_traces_sampling_ratio() -> f64 {
+            1.0
+        }
+
+        #[derive(
+            serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq,
+        )]
+        #[serde(rename_all = "snake_case")]
+        pub enum LogFormat {
+            /// Human-readable lines.
+            #[default]
+            Text,
+            /// One JSON object per line, with the fields of the event at the top level.
+            Json,
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+        pub struct TracingConfig {
+            /// Filter of the events that are logged and exported, in the syntax of `EnvFilter`
+            /// (e.g. `info,seichi_timed_stats_translator=debug`). Defaults to `info`.
+            pub rust_log: Option<String>,
+            #[serde(default)]
+            pub log_format: LogFormat,
+            /// Serve the instrumentation of the runtime to tokio-console, on port 6669 unless
+            /// overridden by the `TOKIO_CONSOLE_BIND` variable.
+            #[serde(default)]
+            pub tokio_console_enabled: bool,
+            /// OTLP/gRPC endpoint that spans are exported to (e.g. `http://tempo:4317`). Spans are
+            /// not exported if unset.
+            pub otel_exporter_otlp_endpoint: Option<String>,
+            #[serde(default = "default_otel_service_name")]
+            pub otel_service_name: String,
+            /// Ratio of traces that are sampled, from 0 to 1. Traces continued from a caller
+            /// follow the sampling decision of the caller.
+            #[serde(default = "default_otel_traces_sampling_ratio")]
+            pub otel_traces_sampling_ratio: f64,
+        }
+
+        impl Settings for TracingConfig {}
+
+        impl TracingConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let config = sources.extract::<Self>()?;
+
+                if !(0.0..=1.0).contains(&config.otel_traces_sampling_ratio) {
+                    return Err(sources.invalid(
+                        "OTEL_TRACES_SAMPLING_RATIO",
+                        format!(
+                            "must be between 0 and 1, got {}",
+                            config.otel_traces_sampling_ratio
+                        ),
+                    ));
+                }
+
+                Ok(config)
+            }
+        }
+    }
+
+    /// Filter of the events that are logged and exported.
+    #[must_use]
+    pub fn env_filter(config: &config::TracingConfig) -> EnvFilter {
+        EnvFilter::new(config.rust_log.as_deref().unwrap_or("info"))
+    }
+
+    type ReloadFilter = Box<dyn Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync>;
+
+    /// Handles of the filters created by [`Self::filter`], which are replaced together.
+    #[derive(Default)]
+    pub struct LogFilterReload {
+        reloads: Vec<ReloadFilter>,
+    }
+
+    impl std::fmt::Debug for LogFilterReload {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("LogFilterReload")
+                .field("filters", &self.reloads.len())
+                .finish()
+        }
+    }
+
+    impl LogFilterReload {
+        /// [`env_filter`] that is replaced on [`Self::reload`].
+        pub fn filter<S>(&mut self, config: &config::TracingConfig) -> reload::Layer<EnvFilter, S>
+        where
+            S: Subscriber,
+        {
+            let (filter, handle) = reload::Layer::new(env_filter(config));
+            self.reloads
+                .push(Box::new(move |filter| handle.reload(filter)));
+
+            filter
+        }
+
+        #[allow(clippy::missing_errors_doc)]
+        pub fn reload(&self, config: &config::TracingConfig) -> anyhow::Result<()> {
+            for reload in &self.reloads {
+                reload(env_filter(config))?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Layer serving tokio-console if enabled. It sees the instrumentation of the runtime
+    /// regardless of [`env_filter`], so the other layers have to be filtered individually.
+    pub fn console_layer<S>(config: &config::TracingConfig) -> Option<impl Layer<S>>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        config.tokio_console_enabled.then(console_subscriber::spawn)
+    }
+
+    /// Layer writing logs to `writer` in the configured format.
+    pub fn log_layer<S, W>(
+        config: &config::TracingConfig,
+        writer: W,
+    ) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+        W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+    {
+        match config.log_format {
+            config::LogFormat::Text => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+            config::LogFormat::Json => tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .json()
+                .flatten_event(true)
+                .boxed(),
+        }
+    }
+
+    /// Layer exporting spans over OTLP, or `None` if no endpoint is configured. Must be called
+    /// within the Tokio runtime, on which spans are exported in batches.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn otlp_layer<S>(
+        config: &config::TracingConfig,
+    ) -> anyhow::Result<Option<OpenTelemetryLayer<S, trace::Tracer>>>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let Some(endpoint) = &config.otel_exporter_otlp_endpoint else {
+            return Ok(None);
+        };
+
+        // Lets traces be continued from callers, and by the game data server on upstream calls
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let tracer = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(endpoint),
+            )
+            .with_trace_config(
+                trace::config()
+                    .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                        config.otel_traces_sampling_ratio,
+                    ))))
+                    .with_resource(Resource::new([KeyValue::new(
+                        "service.name",
+                        config.otel_service_name.clone(),
+                    )])),
+            )
+            .install_batch(opentelemetry::runtime::Tokio)?;
+
+        Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer)))
+    }
+
+    struct MetadataInjector<'a>(&'a mut tonic::metadata::MetadataMap);
+
+    impl Injector for MetadataInjector<'_> {
+        fn set(&mut self, key: &str, value: String) {
+            if let (Ok(key), Ok(value)) = (MetadataKey::from_bytes(key.as_bytes()), value.parse()) {
+                self.0.insert(key, value);
+            }
+        }
+    }
+
+    /// Carries the trace context of `span` in the metadata of an outgoing gRPC request.
+    pub fn inject_trace_context(span: &tracing::Span, metadata: &mut tonic::metadata::MetadataMap) {
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&span.context(), &mut MetadataInjector(metadata));
+        });
+    }
+
+    struct HeaderExtractor<'a>(&'a HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(HeaderName::as_str).collect()
+        }
+    }
+
+    /// Span of an incoming HTTP request, continuing the trace of the caller if it sent a
+    /// `traceparent` header.
+    pub fn http_request_span<B>(request: &Request<B>) -> tracing::Span {
+        let span = tracing::info_span!(
+            "request",
+            method = %request.method(),
+            uri = %request.uri(),
+            otel.kind = "server"
+        );
+        span.set_parent(opentelemetry::global::get_text_map_propagator(
+            |propagator| propagator.extract(&HeaderExtractor(request.headers())),
+        ));
+
+        span
+    }
+
+    /// Flushes the spans that have not been exported yet.
+    pub fn shutdown() {
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
+pub mod infra_error_reporting {
+    use tracing::Subscriber;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    pub mod config {
+        use crate::config::{ConfigSources, Settings};
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        pub struct ErrorReportingConfig {
+            /// DSN of the Sentry project that errors and panics are reported to. Nothing is
+            /// reported if unset.
+            pub sentry_dsn: Option<String>,
+            pub sentry_environment: Option<String>,
+        }
+
+        impl Settings for ErrorReportingConfig {
+            const SECRETS: &'static [&'static str] = &["sentry_dsn"];
+        }
+
+        impl ErrorReportingConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                sources.extract()
+            }
+        }
+    }
+
+    const RELEASE: &str = concat!(
+        env!("CARGO_PKG_NAME"),
+        "@",
+        env!("CARGO_PKG_VERSION"),
+        "+",
+        env!("BUILD_GIT_COMMIT")
+    );
+
+    /// Starts reporting panics to Sentry if a DSN is configured. Reporting stops when the
+    /// returned guard is dropped, after the pending reports are sent.
+    pub fn init(config: &config::ErrorReportingConfig) -> Option<sentry::ClientInitGuard> {
+        let dsn = config.sentry_dsn.as_deref()?;
+
+        Some(sentry::init((
+            dsn,
+            sentry::ClientOptions {
+                release: Some(RELEASE.into()),
+                environment: config.sentry_environment.clone().map(Into::into),
+                ..Default::default()
+            },
+        )))
+    }
+
+    /// Layer reporting error events to Sentry, along with the spans they occurred in, and
+    /// keeping the other events as breadcrumbs. It does nothing unless [`init`] enabled
+    /// reporting.
+    #[must_use]
+    pub fn layer<S>() -> impl Layer<S>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        sentry::integrations::tracing::layer()
+    }
+}
+
+pub mod app {
+    use crate::config::ConfigSources;
+    use crate::domain::{
+        GroupMembershipRepository, PlayerDataKind, PlayerDataRepository, SnapshotPersistence,
+    };
+    use crate::infra_axum_handlers;
+    use crate::infra_axum_handlers::{ConfigReloader, ReloadableConfig, SharedAppState};
+    use crate::infra_error_reporting;
+    use crate::infra_repository_decorators;
+    use crate::infra_repository_impls;
+    use crate::infra_runtime_telemetry;
+    use crate::infra_snapshot_persistence;
+    use crate::infra_systemd;
+    use crate::infra_tracing;
+    use crate::use_cases;
+    use arc_swap::ArcSwap;
+    use clap::Parser;
+    use futures::{Stream, StreamExt};
+    use infra_axum_handlers::SourceAllowlist;
+    use std::convert::Infallible;
+    use std::future::Future;
+    use std::io::Write;
+    use std::net::SocketAddr;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::signal::unix::{signal, SignalKind};
+    use tokio::sync::watch;
+    use tower_http::trace::TraceLayer;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+    use tracing_subscriber::Layer;
+
+    pub mod cli {
+        use std::path::PathBuf;
+
+        #[derive(clap::Parser, Debug)]
+        #[command(
+            version,
+            about = "Translates the game data server into Prometheus metrics"
+        )]
+        pub struct Cli {
+            /// TOML or YAML file that the configuration is read from, below the environment.
+            #[arg(long, global = true, env = "CONFIG_FILE")]
+            pub config: Option<PathBuf>,
+            /// Port the HTTP server listens on, on every interface.
+            #[arg(long, global = true)]
+            pub port: Option<u16>,
+            /// URL of the game data server.
+            #[arg(long, global = true)]
+            pub endpoint: Option<String>,
+            /// Filter of the events that are logged, in the syntax of `RUST_LOG`.
+            #[arg(long, global = true)]
+            pub log_level: Option<String>,
+            #[command(subcommand)]
+            pub command: Option<Command>,
+        }
+
+        #[derive(clap::Subcommand, Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub enum Command {
+            /// Serve the metrics over HTTP.
+            #[default]
+            Serve,
+            /// Validate the configuration and dial the upstream, print a report and exit, with a
+            /// non-zero status if any step failed.
+            Check {
+                /// Also read the first record of a kind of player data from the upstream.
+                #[arg(long)]
+                rpc: bool,
+                /// How long to wait for the upstream to become reachable.
+                #[arg(long, default_value_t = 10)]
+                timeout_seconds: u64,
+            },
+            /// Fetch the player data once and print the exposition to the standard output.
+            RenderOnce,
+        }
+
+        impl Cli {
+            /// Settings given by the flags, which take precedence over every other source.
+            #[must_use]
+            pub fn overrides(&self) -> Vec<(String, String)> {
+                let mut overrides = Vec::new();
+                if let Some(port) = self.port {
+                    overrides.push(("LISTEN_ADDRESS".to_string(), format!("0.0.0.0:{port}")));
+                }
+                if let Some(endpoint) = &self.endpoint {
+                    overrides.push((
+                        "GAME_DATA_SERVER_GRPC_ENDPOINT_URL".to_string(),
+                        endpoint.clone(),
+                    ));
+                }
+                if let Some(log_level) = &self.log_level {
+                    overrides.push(("RUST_LOG".to_string(), log_level.clone()));
+                }
+
+                overrides
+            }
+        }
+    }
+
+    pub mod config {
+        use crate::config::{ConfigSources, Settings};
+        use tokio::runtime::{Builder, Runtime};
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+        pub struct RuntimeConfig {
+            /// Number of threads running the asynchronous tasks. Defaults to the number of CPUs,
+            /// and is ignored when `tokio_single_threaded` is set.
+            pub tokio_worker_threads: Option<usize>,
+            /// Largest number of threads running blocking work such as persisting snapshots.
+            /// Defaults to the limit of Tokio, 512.
+            pub tokio_max_blocking_threads: Option<usize>,
+            /// Run every asynchronous task on the main thread instead of a pool of workers.
+            #[serde(default)]
+            pub tokio_single_threaded: bool,
+        }
+
+        impl Settings for RuntimeConfig {}
+
+        impl RuntimeConfig {
+            #[allow(clippy::missing_errors_doc)]
+            pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+                let config = sources.extract::<Self>()?;
+
+                if config.tokio_worker_threads == Some(0) {
+                    return Err(sources.invalid("TOKIO_WORKER_THREADS", "must be at least 1"));
+                }
+                if config.tokio_max_blocking_threads == Some(0) {
+                    return Err(sources.invalid("TOKIO_MAX_BLOCKING_THREADS", "must be at least 1"));
+                }
+
+                Ok(config)
+            }
+
+            #[allow(clippy::missing_errors_doc)]
+            pub fn build_runtime(&self) -> std::io::Result<Runtime> {
+                let mut builder = if self.tokio_single_threaded {
+                    Builder::new_current_thread()
+                } else {
+                    Builder::new_multi_thread()
+                };
+                if let Some(worker_threads) = self.tokio_worker_threads {
+                    builder.worker_threads(worker_threads);
+                }
+                if let Some(max_blocking_threads) = self.tokio_max_blocking_threads {
+                    builder.max_blocking_threads(max_blocking_threads);
+                }
+
+                builder.enable_all().build()
+            }
+        }
+    }
+
+    type SharedGroupMembershipRepository = Arc<dyn GroupMembershipRepository>;
+
+    /// Builds the repository selected by the configuration, wrapped in the configured decorators,
+    /// together with the source of group memberships if one is configured.
+    fn build_repository(
+        config_sources: &ConfigSources,
+        self_metrics: &prometheus::Registry,
+        health: Arc<use_cases::HealthTracker>,
+    ) -> anyhow::Result<(
+        Arc<dyn PlayerDataRepository>,
+        Option<SharedGroupMembershipRepository>,
+    )> {
+        use infra_repository_impls::config::{
+            GroupMembershipSource, RepositoryConfig, RepositoryMode,
+        };
+
+        let repository_config = RepositoryConfig::load(config_sources)?;
+        let mut upstream_group_memberships: Option<SharedGroupMembershipRepository> = None;
+        let repository: Arc<dyn PlayerDataRepository> = match repository_config.repository_mode {
+            RepositoryMode::Grpc => {
+                let client_config =
+                    infra_repository_impls::config::GrpcClientConfig::load(config_sources)?;
+                let telemetry =
+                    infra_repository_impls::telemetry::UpstreamTelemetry::register(self_metrics)?;
+                let connect = |client_config, telemetry| {
+                    let repository = Arc::new(
+                        infra_repository_impls::GameDataGrpcRepository::initialize_connections_with(
+                            client_config,
+                            telemetry,
+                        )?,
+                    );
+                    repository.spawn_connection_supervisor();
+                    anyhow::Ok(repository)
+                };
+
+                let game_servers = client_config.game_servers()?;
+                if game_servers.is_empty() {
+                    let repository = connect(client_config, telemetry)?;
+                    upstream_group_memberships = Some(repository.clone());
+                    repository
+                } else {
+                    let mut servers = Vec::new();
+                    for (server, client_config) in game_servers {
+                        let repository = connect(client_config, telemetry.clone())?;
+                        upstream_group_memberships.get_or_insert_with(|| repository.clone());
+                        servers.push((server, repository as Arc<dyn PlayerDataRepository>));
+                    }
+                    Arc::new(
+                        infra_repository_decorators::multi_server::MultiServerRepository::new(
+                            servers,
+                        ),
+                    )
+                }
+            }
+            RepositoryMode::Stub => {
+                tracing::warn!("serving fake player data generated in process");
+                Arc::new(infra_repository_impls::stub::StubPlayerDataRepository::new(
+                    repository_config.stub_player_count,
+                ))
+            }
+            RepositoryMode::File => {
+                let path = repository_config
+                    .repository_file_path
+                    .clone()
+                    .unwrap_or_default();
+                tracing::warn!("serving player data read from {}", path);
+                Arc::new(infra_repository_impls::file::FilePlayerDataRepository::new(
+                    path,
+                )?)
+            }
+        };
+        let repository: Arc<dyn PlayerDataRepository> = Arc::new(
+            infra_repository_decorators::instrumented::InstrumentedRepository::register(
+                repository,
+                repository_config.repository_mode.as_str(),
+                self_metrics,
+            )?,
+        );
+
+        let circuit_breaker_config =
+            infra_repository_decorators::circuit_breaker::config::CircuitBreakerConfig::load(
+                config_sources,
+            )?;
+        let repository: Arc<dyn PlayerDataRepository> = if circuit_breaker_config.is_enabled() {
+            Arc::new(
+                infra_repository_decorators::circuit_breaker::CircuitBreakingRepository::new(
+                    repository,
+                    &circuit_breaker_config,
+                    health,
+                ),
+            )
+        } else {
+            repository
+        };
+
+        let group_memberships: Option<Arc<dyn GroupMembershipRepository>> =
+            match repository_config.group_membership_source {
+                GroupMembershipSource::Disabled => None,
+                GroupMembershipSource::Upstream => upstream_group_memberships,
+                GroupMembershipSource::File => Some(Arc::new(
+                    infra_repository_impls::file::FileGroupMembershipRepository::new(
+                        repository_config
+                            .group_membership_file_path
+                            .unwrap_or_default(),
+                    )?,
+                )),
+            };
+
+        Ok((repository, group_memberships))
+    }
+
+    /// Status the process exits with when the configuration is invalid, `EX_CONFIG` of
+    /// `sysexits.h`.
+    const CONFIG_ERROR_EXIT_CODE: i32 = 78;
+
+    /// Interval at which the first snapshot is retried before the service is reported ready.
+    const FIRST_SNAPSHOT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Interval at which [`check`] asks the repository whether the upstream became reachable.
+    const CHECK_READINESS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Reloads the configuration from the sources it was loaded from at startup.
+    #[derive(Debug)]
+    struct AppConfigReloader {
+        config_file: Option<PathBuf>,
+        overrides: Vec<(String, String)>,
+        log_filter: infra_tracing::LogFilterReload,
+    }
+
+    impl ConfigReloader for AppConfigReloader {
+        fn reload(&self) -> anyhow::Result<ReloadableConfig> {
+            let config_sources =
+                ConfigSources::load(self.config_file.as_deref(), self.overrides.clone())?;
+            let reloadable_config = load_reloadable_config(&config_sources)?;
+            self.log_filter
+                .reload(&infra_tracing::config::TracingConfig::load(
+                    &config_sources,
+                )?)?;
+
+            tracing::info!(
+                "reloaded configuration:\n{}",
+                config_sources.effective_summary()
+            );
+            Ok(reloadable_config)
+        }
+    }
+
+    fn load_reloadable_config(config_sources: &ConfigSources) -> anyhow::Result<ReloadableConfig> {
+        Ok(ReloadableConfig {
+            fetch_config: use_cases::config::FetchConfig::load(config_sources)?,
+            cache_config: use_cases::config::CacheConfig::load(config_sources)?,
+            slow_scrape_config: infra_axum_handlers::config::SlowScrapeConfig::load(
+                config_sources,
+            )?,
+        })
+    }
+
+    fn build_shared_state(
+        config_sources: &ConfigSources,
+        config_reloader: Arc<dyn ConfigReloader>,
+    ) -> anyhow::Result<SharedAppState> {
+        let self_metrics = prometheus::Registry::new();
+        infra_runtime_telemetry::TokioRuntimeCollector::register(&self_metrics)?;
+
+        let health = Arc::new(use_cases::HealthTracker::default());
+        infra_axum_handlers::HealthStateCollector::register(&self_metrics, health.clone())?;
+
+        let (repository, group_membership_repository) =
+            build_repository(config_sources, &self_metrics, health.clone())?;
+
+        let derived_stats_config = use_cases::config::DerivedStatsConfig::load(config_sources)?;
+
+        let reloadable_config = load_reloadable_config(config_sources)?;
+        let error_log_config = use_cases::config::ErrorLogConfig::load(config_sources)?;
+        let rendering_config = infra_axum_handlers::config::RenderingConfig::load(config_sources)?;
+        let cache_memory_telemetry =
+            infra_axum_handlers::CacheMemoryTelemetry::register(&self_metrics)?;
+        let snapshot_persistence =
+            infra_snapshot_persistence::config::SnapshotPersistenceConfig::load(config_sources)?
+                .snapshot_persistence_path
+                .map(|path| {
+                    Arc::new(infra_snapshot_persistence::DiskSnapshotPersistence::new(
+                        path,
+                    )) as Arc<dyn SnapshotPersistence>
+                });
+
+        Ok(SharedAppState {
+            repository,
+            group_membership_repository,
+            derived_stats_config,
+            reloadable_config: Arc::new(ArcSwap::from_pointee(reloadable_config)),
+            config_reloader,
+            previous_snapshot: Arc::default(),
+            snapshot_cache: Arc::default(),
+            self_metrics,
+            uuid_pseudonymizer: rendering_config.uuid_pseudonymizer().map(Arc::new),
+            rendering_config,
+            rendered_exposition: Arc::default(),
+            snapshot_persistence,
+            cache_memory_telemetry,
+            error_log: Arc::new(use_cases::RepeatedErrorLog::new(
+                error_log_config.summary_interval(),
+            )),
+            health,
+        })
+    }
+
+    /// Loads every setting up front, so that all the problems of the configuration are reported
+    /// together instead of only the first one met while building the server.
+    /// Loads every setting of the translator, so that their problems are reported together.
+    fn validate_config(config_sources: &ConfigSources) -> Result<(), crate::config::ConfigErrors> {
+        use infra_repository_impls::config::{GrpcClientConfig, RepositoryConfig, RepositoryMode};
+
+        let repository_config = RepositoryConfig::load(config_sources);
+        if repository_config.is_ok_and(|config| config.repository_mode == RepositoryMode::Grpc) {
+            let _ = GrpcClientConfig::load(config_sources);
+        }
+        let _ = infra_repository_decorators::circuit_breaker::config::CircuitBreakerConfig::load(
+            config_sources,
+        );
+        let _ = use_cases::config::DerivedStatsConfig::load(config_sources);
+        let _ = use_cases::config::FetchConfig::load(config_sources);
+        let _ = use_cases::config::CacheConfig::load(config_sources);
+        let _ = use_cases::config::ErrorLogConfig::load(config_sources);
+        let _ = infra_axum_handlers::config::ServerConfig::load(config_sources);
+        let _ = infra_axum_handlers::config::RenderingConfig::load(config_sources);
+        let _ = infra_axum_handlers::config::SlowScrapeConfig::load(config_sources);
+        let _ = infra_axum_handlers::config::AuthConfig::load(config_sources);
+        let _ = infra_axum_handlers::config::SourceAllowlistConfig::load(config_sources);
+        let _ = infra_snapshot_persistence::config::SnapshotPersistenceConfig::load(config_sources);
+
+        config_sources.validate()
+    }
+
+    /// Prints `error` and exits with [`CONFIG_ERROR_EXIT_CODE`], before logging is set up.
+    fn exit_with_config_error(error: &dyn std::fmt::Display) -> ! {
+        eprintln!("{error}");
+        std::process::exit(CONFIG_ERROR_EXIT_CODE)
+    }
+
+    /// Loads `.env` from the working directory or its ancestors into the environment.
+    ///
+    /// Variables that are already set are not overridden. Skipped when `DEPLOYMENT_PROFILE` is
+    /// `production`, where the environment is meant to be complete on its own.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn load_dotenv() -> anyhow::Result<Option<PathBuf>> {
+        if std::env::var("DEPLOYMENT_PROFILE").is_ok_and(|profile| profile == "production") {
+            return Ok(None);
+        }
+
+        match dotenvy::dotenv() {
+            Ok(path) => Ok(Some(path)),
+            Err(e) if e.not_found() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Logging, tracing and error reporting set up by [`init_telemetry`], which stay set up while
+    /// this is alive.
+    pub struct Telemetry {
+        pub log_filter: infra_tracing::LogFilterReload,
+        _error_reporting: Option<sentry::ClientInitGuard>,
+    }
+
+    /// Sets up logging, tracing and error reporting as configured in `config_sources`. Logs are
+    /// written to the standard error if `log_to_stderr` is set, to the standard output otherwise.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn init_telemetry(
+        config_sources: &ConfigSources,
+        log_to_stderr: bool,
+    ) -> anyhow::Result<Telemetry> {
+        // see https://github.com/tokio-rs/axum/blob/79a0a54bc9f0f585c974b5e6793541baff980662/examples/tracing-aka-logging/src/main.rs
+        let tracing_config = infra_tracing::config::TracingConfig::load(config_sources)?;
+        let error_reporting = infra_error_reporting::init(
+            &infra_error_reporting::config::ErrorReportingConfig::load(config_sources)?,
+        );
+        let log_layer = if log_to_stderr {
+            infra_tracing::log_layer(&tracing_config, std::io::stderr)
+        } else {
+            infra_tracing::log_layer(&tracing_config, std::io::stdout)
+        };
+        let mut log_filter = infra_tracing::LogFilterReload::default();
+        tracing_subscriber::registry()
+            .with(infra_tracing::console_layer(&tracing_config))
+            .with(log_layer.with_filter(log_filter.filter(&tracing_config)))
+            .with(
+                infra_tracing::otlp_layer(&tracing_config)?
+                    .map(|layer| layer.with_filter(log_filter.filter(&tracing_config))),
+            )
+            .with(infra_error_reporting::layer().with_filter(log_filter.filter(&tracing_config)))
+            .init();
+
+        Ok(Telemetry {
+            log_filter,
+            _error_reporting: error_reporting,
+        })
+    }
+
+    /// Sets up what every command of the binaries shares, then runs `command` on the runtime.
+    ///
+    /// That is the environment from `.env`, the configuration read from `config_file`, the
+    /// environment and `overrides`, the runtime and the telemetry. The configuration is
+    /// validated as a whole first by `validate`, which loads the settings of the command and
+    /// reports their problems along with those of the settings set up here.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn bootstrap<V, F, Fut>(
+        config_file: Option<&Path>,
+        overrides: Vec<(String, String)>,
+        validate: V,
+        log_to_stderr: bool,
+        command: F,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        V: FnOnce(&ConfigSources) -> Result<(), crate::config::ConfigErrors>,
+        F: FnOnce(ConfigSources, infra_tracing::LogFilterReload) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        let dotenv = load_dotenv()?;
+        let config_sources = match ConfigSources::load(config_file, overrides) {
+            Ok(config_sources) => config_sources,
+            Err(e) => exit_with_config_error(&format!("{e:#}")),
+        };
+        let _ = infra_tracing::config::TracingConfig::load(&config_sources);
+        let _ = infra_error_reporting::config::ErrorReportingConfig::load(&config_sources);
+        let _ = config::RuntimeConfig::load(&config_sources);
+        if let Err(errors) = validate(&config_sources) {
+            exit_with_config_error(&errors);
+        }
+
+        // The runtime is built only now, since it is configured like everything else
+        let runtime = config::RuntimeConfig::load(&config_sources)?.build_runtime()?;
+
+        Ok(runtime.block_on(async move {
+            let mut telemetry = init_telemetry(&config_sources, log_to_stderr)?;
+            if let Some(path) = dotenv {
+                tracing::info!("loaded environment variables from {}", path.display());
+            }
+
+            let log_filter = std::mem::take(&mut telemetry.log_filter);
+            let result = command(config_sources, log_filter).await;
+            infra_tracing::shutdown();
+
+            result
+        })?)
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+        run(&cli::Cli::parse())
+    }
+
+    /// Runs a command of the translator given on the command line.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn run(cli: &cli::Cli) -> Result<(), Box<dyn std::error::Error>> {
+        let command = cli.command.unwrap_or_default();
+        let config_reloader = |log_filter| {
+            Arc::new(AppConfigReloader {
+                config_file: cli.config.clone(),
+                overrides: cli.overrides(),
+                log_filter,
+            })
+        };
+
+        // The exposition printed by render-once must not be interleaved with logs
+        bootstrap(
+            cli.config.as_deref(),
+            cli.overrides(),
+            validate_config,
+            command == cli::Command::RenderOnce,
+            |config_sources, log_filter| async move {
+                let config_reloader = config_reloader(log_filter);
+                match command {
+                    cli::Command::Serve => serve(&config_sources, config_reloader).await,
+                    cli::Command::Check {
+                        rpc,
+                        timeout_seconds,
+                    } => {
+                        check(
+                            &config_sources,
+                            config_reloader,
+                            rpc,
+                            Duration::from_secs(timeout_seconds),
+                        )
+                        .await
+                    }
+                    cli::Command::RenderOnce => render_once(&config_sources, config_reloader).await,
+                }
+            },
+        )
+    }
+
+    /// Prints the outcome of a step of [`check`], returning whether it succeeded.
+    fn report_check_step<T>(step: &str, result: &anyhow::Result<T>) -> bool {
+        match result {
+            Ok(_) => println!("[ ok ] {step}"),
+            Err(e) => println!("[FAIL] {step}: {e:#}"),
+        }
+
+        result.is_ok()
+    }
+
+    /// Waits until the repository reports that it can reach its data source, returning the last
+    /// reason it could not if `timeout` elapses first.
+    async fn wait_until_ready(
+        repository: &dyn PlayerDataRepository,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            match repository.check_readiness().await {
+                Ok(()) => return Ok(()),
+                Err(e) if tokio::time::Instant::now() >= deadline => {
+                    return Err(e.context(format!("not ready after {timeout:?}")))
+                }
+                Err(_) => tokio::time::sleep(CHECK_READINESS_POLL_INTERVAL).await,
+            }
+        }
+    }
+
+    /// Reads the first vote count, cancelling the rest of the stream.
+    async fn read_first_record(
+        repository: &dyn PlayerDataRepository,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        tokio::time::timeout(
+            timeout,
+            repository.get_all_of(PlayerDataKind::VoteCount).next(),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("no response after {timeout:?}"))?
+        .transpose()?;
+
+        Ok(())
+    }
+
+    /// Validates every setting by building everything the server would be built from, then
+    /// checks that the upstream can be dialed and, if `rpc` is set, that it answers a request.
+    async fn check(
+        config_sources: &ConfigSources,
+        config_reloader: Arc<dyn ConfigReloader>,
+        rpc: bool,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let shared_state = build_shared_state(config_sources, config_reloader).and_then(|state| {
+            infra_axum_handlers::config::ServerConfig::load(config_sources)?;
+            Ok(state)
+        });
+        println!("{}", config_sources.effective_summary());
+        if !report_check_step("configuration", &shared_state) {
+            anyhow::bail!("the configuration is invalid");
+        }
+        let repository = shared_state?.repository;
+
+        let mut passed = report_check_step(
+            "upstream connection",
+            &wait_until_ready(repository.as_ref(), timeout).await,
+        );
+        if rpc && passed {
+            passed = report_check_step(
+                "upstream request",
+                &read_first_record(repository.as_ref(), timeout).await,
+            );
+        }
+
+        if !passed {
+            anyhow::bail!("the upstream is unavailable");
+        }
+        Ok(())
+    }
+
+    async fn render_once(
+        config_sources: &ConfigSources,
+        config_reloader: Arc<dyn ConfigReloader>,
+    ) -> anyhow::Result<()> {
+        let shared_state = build_shared_state(config_sources, config_reloader)?;
+        let snapshot = infra_axum_handlers::snapshot_use_case(&shared_state)
+            .get_snapshot()
+            .await?;
+        let chunks = infra_axum_handlers::presenter::present_player_data_as_prometheus_metrics(
+            &snapshot,
+            shared_state.repository.connection_state(),
+            infra_axum_handlers::presenter::RecordPresentation {
+                uuid_pseudonymizer: None,
+                rank_series_top_n: shared_state.derived_stats_config.rank_series_top_n,
+                player_names: shared_state.rendering_config.player_name_label,
+            },
+            &mut infra_axum_handlers::presenter::PlayerRecordFragments::default(),
+        )?;
+
+        let mut stdout = std::io::stdout().lock();
+        for chunk in chunks {
+            stdout.write_all(&chunk)?;
+        }
+        stdout.flush()?;
+
+        Ok(())
+    }
+
+    /// Routes every endpoint to its handler, behind the middlewares configured for it.
+    ///
+    /// Returns the router of the public listener, and the router of the internal listener if
+    /// one is configured.
+    fn build_routers(
+        shared_state: &SharedAppState,
+        config_sources: &ConfigSources,
+        server_config: &infra_axum_handlers::config::ServerConfig,
+    ) -> anyhow::Result<(axum::Router, Option<axum::Router>)> {
+        use infra_axum_handlers::{
+            handle_get_groups, handle_get_internal_metrics, handle_get_liveness,
+            handle_get_metrics, handle_get_player, handle_get_readiness, handle_get_summary,
+            handle_post_admin_reload,
+        };
+
+        use axum::middleware;
+        use axum::routing::{get, post};
+        use axum::Router;
+
+        let http_telemetry =
+            infra_axum_handlers::HttpTelemetry::register(&shared_state.self_metrics)?;
+
+        let auth_config = infra_axum_handlers::config::AuthConfig::load(config_sources)?;
+        let authenticator = infra_axum_handlers::auth::RequestAuthenticator::new(
+            auth_config.bearer_token(),
+            auth_config.basic_credentials(),
+        );
+        let authenticated_routes = Router::new()
+            .route("/metrics", get(handle_get_metrics(shared_state.clone())))
+            .route(
+                "/api/v1/players/:uuid",
+                get(handle_get_player(shared_state.clone())),
+            )
+            .route(
+                "/api/v1/groups",
+                get(handle_get_groups(shared_state.clone())),
+            )
+            .route(
+                "/api/v1/summary",
+                get(handle_get_summary(shared_state.clone())),
+            )
+            .route_layer(middleware::from_fn(move |request, next| {
+                authenticator.clone().check(request, next)
+            }));
+
+        let source_allowlist_config =
+            infra_axum_handlers::config::SourceAllowlistConfig::load(config_sources)?;
+        let source_allowlist = infra_axum_handlers::SourceAllowlist::new(
+            source_allowlist_config.allowed_networks(),
+            source_allowlist_config.trusted_proxy_depth,
+        );
+
+        let max_request_body_bytes = server_config.max_request_body_bytes;
+        let admin_routes = Router::new()
+            .route(
+                "/admin/reload",
+                post(handle_post_admin_reload(shared_state.clone())),
+            )
+            .route_layer(middleware::from_fn(move |request, next| {
+                infra_axum_handlers::limit_request_body(max_request_body_bytes, request, next)
+            }));
+
+        let reject_request_body = middleware::from_fn(|request, next| {
+            infra_axum_handlers::limit_request_body(0, request, next)
+        });
+        let public_routes = authenticated_routes.route_layer(reject_request_body);
+        let internal_routes = Router::new()
+            .route("/healthz", get(handle_get_liveness()))
+            .route(
+                "/internal/metrics",
+                get(handle_get_internal_metrics(shared_state.clone())),
+            )
+            .route("/readyz", get(handle_get_readiness(shared_state.clone())))
+            .route_layer(reject_request_body)
+            .merge(admin_routes);
+
+        let instrument = |router: Router, source_allowlist: Option<SourceAllowlist>| {
+            let http_telemetry = http_telemetry.clone();
+            let router = router.route_layer(middleware::from_fn(move |request, next| {
+                http_telemetry.clone().track(request, next)
+            }));
+            let router = match source_allowlist {
+                Some(source_allowlist) => {
+                    router.layer(middleware::from_fn(move |request, next| {
+                        source_allowlist.clone().check(request, next)
+                    }))
+                }
+                None => router,
+            };
+
+            router
+                .layer(TraceLayer::new_for_http().make_span_with(infra_tracing::http_request_span))
+        };
+
+        Ok(match server_config.internal_listen_address {
+            Some(_) => (
+                instrument(public_routes, Some(source_allowlist)),
+                Some(instrument(internal_routes, None)),
+            ),
+            None => (
+                instrument(public_routes.merge(internal_routes), Some(source_allowlist)),
+                None,
+            ),
+        })
+    }
+
+    /// Serves `router` on the connections of a listener until `shutdown` turns true.
+    async fn serve_connections<S>(
+        connections: S,
+        router: axum::Router,
+        server_config: &infra_axum_handlers::config::ServerConfig,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> hyper::Result<()>
+    where
+        S: Stream<Item = infra_axum_handlers::listener::Connection> + Send,
+    {
+        let mut server = axum::Server::builder(hyper::server::accept::from_stream(
+            connections.map(Ok::<_, Infallible>),
+        ));
+        if let Some(max_header_bytes) = server_config.max_header_bytes {
+            server = server
+                .http1_max_buf_size(max_header_bytes)
+                .http2_max_header_list_size(u32::try_from(max_header_bytes).unwrap_or(u32::MAX));
+        }
+
+        server
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(async move {
+                let _ = shutdown.wait_for(|started| *started).await;
+            })
+            .await
+    }
+
+    async fn serve(
+        config_sources: &ConfigSources,
+        config_reloader: Arc<dyn ConfigReloader>,
+    ) -> anyhow::Result<()> {
+        let shared_state = build_shared_state(config_sources, config_reloader)?;
+        infra_axum_handlers::spawn_reload_on_hangup(shared_state.clone())?;
+        shared_state.error_log.clone().spawn_periodic_flush();
+
+        if infra_axum_handlers::snapshot_use_case(&shared_state).restore_persisted_snapshot()? {
+            tracing::info!("restored the snapshot persisted by a previous run");
+        }
+
+        if let Some(interval) = shared_state.rendering_config.background_render_interval() {
+            infra_axum_handlers::spawn_background_rendering(shared_state.clone(), interval);
+        } else if let Some(interval) = infra_systemd::watchdog_interval() {
+            // Without the background rendering, nothing runs periodically but the runtime itself
+            tokio::spawn(async move {
+                loop {
+                    infra_systemd::pet_watchdog();
+                    tokio::time::sleep(interval).await;
+                }
+            });
+        }
+
+        let server_config = infra_axum_handlers::config::ServerConfig::load(config_sources)?;
+        let (public_router, internal_router) =
+            build_routers(&shared_state, config_sources, &server_config)?;
+
+        tracing::info!(
+            "effective configuration:\n{}",
+            config_sources.effective_summary()
+        );
+
+        let addr = server_config.listen_address;
+        let connections = infra_axum_handlers::listener::bind(
+            addr,
+            server_config.max_connections,
+            server_config.tls_acceptor()?,
+        )
+        .await?;
+        tracing::info!("listening on {}", addr);
+        let internal_connections = match server_config.internal_listen_address {
+            Some(internal_addr) => {
+                let connections =
+                    infra_axum_handlers::listener::bind(internal_addr, None, None).await?;
+                tracing::info!("listening on {} for internal endpoints", internal_addr);
+                Some(connections)
+            }
+            None => None,
+        };
+
+        let (shutdown_started, shutdown) = watch::channel(false);
+        let shutdown_signal = shutdown_signal()?;
+        tokio::spawn(async move {
+            shutdown_signal.await;
+            let _ = shutdown_started.send(true);
+        });
+
+        let public_server =
+            serve_connections(connections, public_router, &server_config, shutdown.clone());
+        let internal_server = async {
+            match internal_connections.zip(internal_router) {
+                Some((connections, router)) => {
+                    serve_connections(connections, router, &server_config, shutdown.clone()).await
+                }
+                None => Ok(()),
+            }
+        };
+        tokio::spawn(notify_ready_after_first_snapshot(shared_state));
+
+        let grace_period = server_config.shutdown_grace_period();
+        let mut shutdown = shutdown.clone();
+        let grace_period_elapsed = async move {
+            if shutdown.wait_for(|started| *started).await.is_err() {
+                return std::future::pending().await;
+            }
+            tracing::info!(
+                "stopped accepting connections, waiting up to {:?} for requests in flight",
+                grace_period
+            );
+            tokio::time::sleep(grace_period).await;
+        };
+
+        // The server drains the connections it has accepted until the grace period elapses
+        tokio::select! {
+            result = futures::future::try_join(public_server, internal_server) => {
+                result?;
+            }
+            () = grace_period_elapsed => {
+                tracing::warn!("dropping the requests still in flight after the grace period");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tells systemd that the service has started once a snapshot is available, retrying the
+    /// first refresh until it succeeds.
+    async fn notify_ready_after_first_snapshot(shared_state: SharedAppState) {
+        while let Err(e) = infra_axum_handlers::snapshot_use_case(&shared_state)
+            .get_snapshot()
+            .await
+        {
+            shared_state
+                .error_log
+                .error("failed to load the first snapshot", &e);
+            tokio::time::sleep(FIRST_SNAPSHOT_RETRY_INTERVAL).await;
+        }
+
+        infra_systemd::notify_ready();
+    }
+
+    /// Resolves once the process is asked to terminate by SIGTERM or SIGINT.
+    fn shutdown_signal() -> anyhow::Result<impl Future<Output = ()>> {
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut interrupt = signal(SignalKind::interrupt())?;
+
+        Ok(async move {
+            let name = tokio::select! {
+                _ = terminate.recv() => "SIGTERM",
+                _ = interrupt.recv() => "SIGINT",
+            };
+            tracing::info!("received {}, shutting down", name);
+        })
+    }
+}